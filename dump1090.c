@@ -3,6 +3,8 @@
  * \ingroup Main
  * \brief   Dump1090, a Mode-S messages decoder for RTLSDR devices.
  */
+#define _CRT_RAND_S   /* for 'rand_s()' */
+
 #include <stdio.h>
 #include <stdlib.h>
 #include <string.h>
@@ -20,6 +22,9 @@
 #include <conio.h>
 #include <process.h>
 
+#include <winsock2.h>
+#include <iphlpapi.h>
+
 #include "misc.h"
 #include "trace.h"
 #include "sdrplay.h"
@@ -107,6 +112,7 @@
 #define MODES_INTERACTIVE_ROWS           15   /* Rows on screen */
 #define MODES_INTERACTIVE_TTL         60000   /* TTL (msec) before being removed */
 #define MODES_CONNECT_TIMEOUT          5000   /* msec timeout for an active connect */
+#define MODES_NET_RATE_INTERVAL       10000   /* msec between updates of the network send rates */
 
 #define MG_NET_POLL_TIME             (MODES_INTERACTIVE_REFRESH_TIME / 2)
 
@@ -129,6 +135,7 @@ typedef struct modeS_message {
         bool     CRC_ok;                     /**< True if CRC was valid */
         uint32_t CRC;                        /**< Message CRC */
         double   sig_level;                  /**< RSSI, in the range [0..1], as a fraction of full-scale power */
+        uint64_t timestamp_msg;              /**< 12 MHz MLAT timestamp (48 bits). 0 if unknown. */
         int      error_bit;                  /**< Bit corrected. -1 if no bit corrected. */
         int      AA1, AA2, AA3;              /**< ICAO Address bytes 1, 2 and 3 */
         bool     phase_corrected;            /**< True if phase correction was applied. */
@@ -157,6 +164,10 @@ typedef struct modeS_message {
         int  vert_rate_sign;                 /**< Vertical rate sign. */
         int  vert_rate;                      /**< Vertical rate. */
         int  velocity;                       /**< Computed from EW and NS velocity. */
+        int  ADSB_version;                   /**< ME type 31: ADS-B version number. */
+        int  NACp;                           /**< ME type 31: Navigation Accuracy Category for position. */
+        int  SIL;                            /**< ME type 31: Source Integrity Level. */
+        bool NIC_supp_A;                     /**< ME type 31: NIC supplement-A. */
 
         /** DF4, DF5, DF20, DF21
          */
@@ -165,6 +176,21 @@ typedef struct modeS_message {
         int UM_status;                       /**< Request extraction of downlink request. */
         int identity;                        /**< 13 bits identity (Squawk). */
 
+        /** DF20, DF21 Comm-B (the 56 bits MB field)
+         */
+        int      BDS;                        /**< The register (e.g. 0x40 for BDS 4,0) guessed from the MB field. 0 if unknown. */
+        uint32_t BDS_capability;             /**< BDS 1,7: Bit-mask of the GICB registers supported. */
+        int      sel_altitude;               /**< BDS 4,0: MCP/FCU selected altitude (feet). */
+        double   baro_setting;               /**< BDS 4,0: barometric pressure setting (mb). 0 if not available. */
+        double   roll;                       /**< BDS 5,0: roll angle (degrees, negative is left wing down). */
+        double   true_track;                 /**< BDS 5,0: true track angle (degrees). */
+        int      ground_speed;               /**< BDS 5,0: ground speed (knots). */
+        int      true_airspeed;              /**< BDS 5,0: true airspeed (knots). 0 if not available. */
+        double   mag_heading;                /**< BDS 6,0: magnetic heading (degrees). */
+        int      IAS;                        /**< BDS 6,0: indicated airspeed (knots). */
+        double   mach;                       /**< BDS 6,0: Mach number. */
+        int      baro_vert_rate;             /**< BDS 6,0: barometric altitude rate (feet/min). */
+
         /** Fields used by multiple message types.
          */
         int altitude, unit;
@@ -177,10 +203,14 @@ void      modeS_user_message (const modeS_message *mm);
 void      set_est_home_distance (aircraft *a, uint64_t now);
 void      spherical_to_cartesian (cartesian_t *cart, pos_t point);
 bool      ICAO_is_military (uint32_t addr);
+int       aircraft_numbers (void);
 
+void      modeS_init_syndromes (void);
 int       fix_single_bit_errors (uint8_t *msg, int bits);
 int       fix_two_bits_errors (uint8_t *msg, int bits);
 int       detect_modeS (uint16_t *m, uint32_t mlen);
+int       detect_modeAC (const uint16_t *m, uint32_t mlen);
+void      modeAC_user_reply (int a, int b, int c, int d);
 void      decode_hex_message (mg_iobuf *msg, int loop_cnt);
 void      decode_SBS_message (mg_iobuf *msg, int loop_cnt);
 int       modeS_message_len_by_type (int type);
@@ -188,9 +218,37 @@ uint16_t *compute_magnitude_vector (const uint8_t *data);
 void      background_tasks (void);
 void      modeS_exit (void);
 void      sigint_handler (int sig);
+void      show_DF_statistics (void);
+void      show_AC_statistics (void);
+void      log_aircraft_RSSI (const aircraft *a);
+void      set_RSSI_sector (const aircraft *a, double dB);
+double    aircraft_RSSI_trend (const aircraft *a);
+void      show_RSSI_statistics (void);
+void      net_rates_update (uint64_t now);
+void      interactive_show_net_rates (void);
+bool      position_plausible (aircraft *a, pos_t pos);
+void      track_plausible (aircraft *a, pos_t pos);
+void      aircraft_set_suspect (aircraft *a, a_suspect_t reason);
+int       decode_hex_arg (const char *arg);
+bool      web_auth_check (const char *auth);
+#if defined(USE_TESTS)
+int       modeS_tests (void);
+#endif
+int       get_NIC (int ME_type, bool NIC_supp_A);
+int       modeS_init_record (void);
+int       modeS_init_pos_report (void);
+int       modeS_init_rate_csv (void);
+uint64_t  modeS_msg_time (const modeS_message *mm, uint64_t now);
+void      rate_update (const modeS_message *mm);
+void      show_rate_chart (void);
+void      position_rejected (const aircraft *a, pos_t pos, const char *reason, double distance, double limit);
+bool      modeS_record_samples (const uint8_t *buf, uint32_t len);
 
 u_short        handler_port (intptr_t service);
 const char    *handler_descr (intptr_t service);
+const char    *web_auth_random (void);
+const char    *aircraft_suspect_name (a_suspect_t reason);
+void           handler_set_port (intptr_t service, const char *arg);
 mg_connection *handler_conn (intptr_t service);
 void           connection_read (connection *conn, msg_handler handler, bool is_server);
 void           connection_send (intptr_t service, const void *msg, size_t len);
@@ -884,6 +942,8 @@ int modeS_init (void)
     spherical_to_cartesian (&Modes.home_pos_cart, Modes.home_pos);
   }
 
+  modeS_init_syndromes();
+
   InitializeCriticalSection (&Modes.data_mutex);
   InitializeCriticalSection (&Modes.print_mutex);
   signal (SIGINT, sigint_handler);
@@ -1054,6 +1114,232 @@ int modeS_init_RTLSDR (void)
   return (0);
 }
 
+/**
+ * Open the `--record` file for the raw 8-bit IQ samples and write a
+ * `<file>.txt` with the settings needed to replay it later.
+ */
+int modeS_init_record (void)
+{
+  char   info_file [MG_PATH_MAX];
+  char   gain [10], date [30];
+  time_t now = time (NULL);
+  FILE  *info;
+
+  Modes.record = fopen (Modes.record_file, "wb");
+  if (!Modes.record)
+  {
+    LOG_STDERR ("Failed to create \"%s\": %s.\n", Modes.record_file, strerror(errno));
+    return (1);
+  }
+
+  snprintf (info_file, sizeof(info_file), "%s.txt", Modes.record_file);
+  info = fopen (info_file, "wt");
+  if (!info)
+  {
+    LOG_STDERR ("Failed to create \"%s\": %s.\n", info_file, strerror(errno));
+    return (1);
+  }
+
+  if (Modes.gain_auto)
+       strcpy (gain, "auto");
+  else snprintf (gain, sizeof(gain), "%.1f", (double)Modes.gain / 10.0);
+
+  strftime (date, sizeof(date), "%Y-%m-%d %H:%M:%S", localtime(&now));
+
+  fprintf (info, "file:        %s\n"
+                 "format:      8-bit unsigned IQ\n"
+                 "device:      %s\n"
+                 "frequency:   %u Hz\n"
+                 "sample-rate: %u S/s\n"
+                 "gain:        %s dB\n"
+                 "started:     %s\n",
+           basename(Modes.record_file), Modes.selected_dev ? Modes.selected_dev : "default",
+           Modes.freq, Modes.sample_rate, gain, date);
+  fclose (info);
+
+  LOG_STDOUT ("Recording IQ samples to \"%s\" (settings in \"%s\").\n", Modes.record_file, info_file);
+  return (0);
+}
+
+/**
+ * Write the IQ samples to the `--record` file.
+ * Nothing more is written when the `--record-time` is reached.
+ *
+ * On a write error (e.g. a full disk), the file is closed and the
+ * decoding continues without a recording.
+ *
+ * \retval true when the `--record-time` is reached and the program should stop.
+ */
+bool modeS_record_samples (const uint8_t *buf, uint32_t len)
+{
+  /* 2 bytes (I and Q) per sample.
+   */
+  uint64_t max_bytes = 2ULL * Modes.sample_rate * Modes.record_sec;
+
+  if (Modes.record_error || (Modes.record_sec > 0 && Modes.record_bytes >= max_bytes))
+     return (false);   /* already stopped */
+
+  if (fwrite(buf, 1, len, Modes.record) != len)
+  {
+    LOG_STDERR ("Failed to write to \"%s\": %s. Stopping the recording.\n",
+                Modes.record_file, strerror(errno));
+    Modes.record_error = true;
+    fclose (Modes.record);
+    Modes.record = NULL;
+    return (false);
+  }
+  Modes.record_bytes += len;
+  return (Modes.record_sec > 0 && Modes.record_bytes >= max_bytes);
+}
+
+/**
+ * Return the time of a message in milli-seconds.
+ *
+ * An `--infile` is read faster than real-time. So for these, use the time
+ * of the message in the recording; from the 12 MHz MLAT timestamp.
+ * Otherwise return `now`.
+ */
+uint64_t modeS_msg_time (const modeS_message *mm, uint64_t now)
+{
+  if (Modes.infile && mm->timestamp_msg)
+     return (mm->timestamp_msg / 12000);
+  return (now);
+}
+
+/**
+ * The Downlink Formats with a column in the `--rate-csv` file.
+ * The other DFs are summed in the `other` column.
+ */
+static const int rate_DFs[] = { 0, 4, 5, 11, 16, 17, 18, 20, 21 };
+
+/**
+ * Create the `--rate-csv` file and write the CSV header.
+ */
+int modeS_init_rate_csv (void)
+{
+  int i;
+
+  Modes.rate_csv = fopen (Modes.rate_csv_file, "wt");
+  if (!Modes.rate_csv)
+  {
+    LOG_STDERR ("Failed to create \"%s\": %s.\n", Modes.rate_csv_file, strerror(errno));
+    return (1);
+  }
+  fputs ("minute,messages,aircrafts", Modes.rate_csv);
+  for (i = 0; i < DIM(rate_DFs); i++)
+      fprintf (Modes.rate_csv, ",DF%d", rate_DFs[i]);
+  fputs (",other\n", Modes.rate_csv);
+  return (0);
+}
+
+/**
+ * Write the current minute to the `--rate-csv` file, add it to the
+ * history for the chart and start on the next minute.
+ */
+static void rate_next_minute (void)
+{
+  rate_minute *r = &Modes.rate;
+  rate_minute *history;
+  uint64_t     minute = r->minute;
+  uint32_t     other = r->messages;
+  int          i;
+
+  fprintf (Modes.rate_csv, "%llu,%u,%u", r->minute, r->messages, r->aircrafts);
+  for (i = 0; i < DIM(rate_DFs); i++)
+  {
+    fprintf (Modes.rate_csv, ",%u", r->DF[rate_DFs[i]]);
+    other -= r->DF [rate_DFs[i]];
+  }
+  fprintf (Modes.rate_csv, ",%u\n", other);
+
+  history = realloc (Modes.rate_history, (Modes.rate_history_num + 1) * sizeof(*history));
+  if (history)
+  {
+    Modes.rate_history = history;
+    Modes.rate_history [Modes.rate_history_num++] = *r;
+  }
+
+  memset (r, '\0', sizeof(*r));
+  memset (Modes.rate_addr, '\0', sizeof(Modes.rate_addr));
+  r->minute = minute + 1;
+}
+
+/**
+ * Count a message for the `--rate-csv` file.
+ * The minutes are from the start; of the recording with `--infile`.
+ * A minute without any messages gets a row too.
+ */
+void rate_update (const modeS_message *mm)
+{
+  uint64_t minute = modeS_msg_time (mm, MSEC_TIME() - Modes.start_time) / 60000;
+  uint32_t addr   = (mm->AA1 << 16) | (mm->AA2 << 8) | mm->AA3;
+  uint32_t h;
+
+  while (minute > Modes.rate.minute)
+    rate_next_minute();
+
+  Modes.rate.messages++;
+  Modes.rate.DF [mm->msg_type & 31]++;
+
+  if (addr == 0 || Modes.rate.aircrafts >= MODES_RATE_ADDRESSES - 1)
+     return;
+
+  /* Linear probing in the hash-set of addresses.
+   */
+  h = ICAO_cache_hash_address (addr) & (MODES_RATE_ADDRESSES - 1);
+  while (Modes.rate_addr[h] && Modes.rate_addr[h] != addr)
+     h = (h + 1) & (MODES_RATE_ADDRESSES - 1);
+
+  if (Modes.rate_addr[h] == 0)
+  {
+    Modes.rate_addr [h] = addr;
+    Modes.rate.aircrafts++;
+  }
+}
+
+/**
+ * Print a chart of the messages per minute written to the `--rate-csv` file.
+ */
+void show_rate_chart (void)
+{
+  const rate_minute *r;
+  uint32_t           max = 0;
+  size_t             i;
+  int                len;
+
+  for (i = 0; i < Modes.rate_history_num; i++)
+      if (Modes.rate_history[i].messages > max)
+         max = Modes.rate_history[i].messages;
+
+  LOG_STDOUT ("Messages per minute:\n");
+  LOG_STDOUT ("  minute  messages  aircrafts\n");
+  for (i = 0; i < Modes.rate_history_num; i++)
+  {
+    char bar [61];
+
+    r   = Modes.rate_history + i;
+    len = max ? (int) ((uint64_t)r->messages * (sizeof(bar) - 1) / max) : 0;
+    memset (bar, '#', len);
+    bar [len] = '\0';
+    LOG_STDOUT ("  %6llu  %8u  %9u  %s\n", r->minute, r->messages, r->aircrafts, bar);
+  }
+}
+
+/**
+ * Create the `--pos-report` file and write the CSV header.
+ */
+int modeS_init_pos_report (void)
+{
+  Modes.pos_report = fopen (Modes.pos_report_file, "wt");
+  if (!Modes.pos_report)
+  {
+    LOG_STDERR ("Failed to create \"%s\": %s.\n", Modes.pos_report_file, strerror(errno));
+    return (1);
+  }
+  fputs ("time,ICAO,lat,lon,reason,distance_km,limit_km\n", Modes.pos_report);
+  return (0);
+}
+
 /**
  * This reading callback gets data from the RTLSDR or SDRplay API asynchronously.
  * We then populate the data buffer.
@@ -1081,6 +1367,12 @@ void rx_callback (uint8_t *buf, uint32_t len, void *ctx)
   memcpy (Modes.data + 4*(MODES_FULL_LEN-1), buf, len);
   Modes.data_ready = true;
   LeaveCriticalSection (&Modes.data_mutex);
+
+  /* `buf` is valid until we return. Write it outside the Mutex, so a slow
+   * disk does not block the decoding thread.
+   */
+  if (Modes.record && modeS_record_samples(buf, len))
+     sigint_handler (0);   /* `--record-time` reached; break out of main_data_loop() */
 }
 
 /**
@@ -1134,6 +1426,9 @@ int read_from_data_file (void)
 
      compute_magnitude_vector (Modes.data);
      detect_modeS (Modes.magnitude, Modes.data_len/2);
+     if (Modes.mode_AC)
+        detect_modeAC (Modes.magnitude, Modes.data_len/2);
+     Modes.sample_counter += MODES_DATA_LEN / 2;
      background_tasks();
 
      if (Modes.exit || Modes.fd == STDIN_FILENO)
@@ -1222,6 +1517,10 @@ void main_data_loop (void)
 #endif
       detect_modeS (Modes.magnitude, Modes.data_len/2);
 
+    if (Modes.mode_AC)
+       detect_modeAC (Modes.magnitude, Modes.data_len/2);
+
+    Modes.sample_counter += MODES_DATA_LEN / 2;
     LeaveCriticalSection (&Modes.data_mutex);
 
     if (/* rc > 0 && */ Modes.max_messages > 0)
@@ -1462,92 +1761,215 @@ int modeS_message_len_by_type (int type)
 }
 
 /**
- * Try to fix single bit errors using the checksum. On success modifies
- * the original buffer with the fixed version, and returns the position
- * of the error bit. Otherwise if fixing failed, -1 is returned.
+ * \typedef syndrome_info
+ * The CRC syndrome for an error in 1 or 2 bit positions.
+ * Sorted on `syndrome` for a `bsearch()`.
  */
-int fix_single_bit_errors (uint8_t *msg, int bits)
-{
-  int     i;
-  uint8_t aux [MODES_LONG_MSG_BITS/8];
+typedef struct syndrome_info {
+        uint32_t syndrome;
+        int      error_bits;   /**< Bit position(s) as returned from `fix_single_bit_errors()` or `fix_two_bits_errors()`. */
+      } syndrome_info;
 
-  for (i = 0; i < bits; i++)
-  {
-    int      byte = i / 8;
-    int      bitmask = 1 << (7-(i % 8));
-    uint32_t crc1, crc2;
+/**
+ * The syndrome tables for 1 and 2 bit errors in short and long messages.
+ */
+static syndrome_info syndrome_short_1 [MODES_SHORT_MSG_BITS];
+static syndrome_info syndrome_long_1 [MODES_LONG_MSG_BITS];
+static syndrome_info syndrome_short_2 [MODES_SHORT_MSG_BITS * (MODES_SHORT_MSG_BITS-1) / 2];
+static syndrome_info syndrome_long_2 [MODES_LONG_MSG_BITS * (MODES_LONG_MSG_BITS-1) / 2];
 
-    memcpy (aux, msg, bits/8);
-    aux[byte] ^= bitmask;   /* Flip j-th bit. */
+/**
+ * Cleared by `modeS_init_syndromes()` if the 1 or 2 bit tables have a duplicate syndrome.
+ * The error correction with that table is then disabled.
+ */
+static bool syndromes_1_ok = true;
+static bool syndromes_2_ok = true;
 
-    crc1 = ((uint32_t)aux[(bits/8)-3] << 16) |
-           ((uint32_t)aux[(bits/8)-2] << 8) |
-            (uint32_t)aux[(bits/8)-1];
-    crc2 = modeS_checksum (aux, bits);
+/**
+ * Return the syndrome (the XOR of the received and computed CRC)
+ * caused by flipping bit `i` in a message of `bits` length.
+ *
+ * A data-bit changes the computed CRC. A bit in the 24 bit CRC-field
+ * changes the received CRC.
+ */
+static uint32_t bit_syndrome (int i, int bits)
+{
+  int offset = MODES_LONG_MSG_BITS - bits;
 
-    if (crc1 == crc2)
-    {
-      /* The error is fixed. Overwrite the original buffer with
-       * the corrected sequence, and returns the error bit
-       * position.
-       */
-      memcpy (msg, aux, bits/8);
-      return (i);
-    }
-  }
-  return (-1);
+  if (i >= bits - 24)
+     return (1 << (bits - 1 - i));
+  return (modeS_checksum_table [i + offset]);
 }
 
 /**
- * Similar to `fix_single_bit_errors()` but try every possible two bit combination.
+ * The compare function for `qsort()` and `bsearch()`.
  *
- * This is very slow and should be tried only against DF17 messages that
- * don't pass the checksum, and only in Aggressive Mode.
+ * The CRC-24 of Mode S gives a unique syndrome for every 1 bit and every
+ * 2 bit error in a 56 or 112 bit message. So there is at most one match
+ * and no tie-break on the bit position(s) is needed.
+ * `syndromes_unique()` checks this in `modeS_init_syndromes()` on every startup.
  */
-int fix_two_bits_errors (uint8_t *msg, int bits)
+static int syndrome_compare (const void *_a, const void *_b)
+{
+  const syndrome_info *a = (const syndrome_info*) _a;
+  const syndrome_info *b = (const syndrome_info*) _b;
+
+  if (a->syndrome < b->syndrome)
+     return (-1);
+  if (a->syndrome > b->syndrome)
+     return (1);
+  return (0);
+}
+
+/**
+ * Fill `table` with the syndromes of all two bit errors in a message of `bits` length.
+ *
+ * \retval the number of entries set.
+ */
+static int init_two_bits_syndromes (syndrome_info *table, int bits)
 {
-  int     j, i;
-  uint8_t aux [MODES_LONG_MSG_BITS/8];
+  int i, j, n = 0;
 
+  /* Return the two bits as a 16 bit integer by shifting 'i' on the left.
+   * This is possible since 'i' will always be non-zero because i starts from j+1.
+   */
   for (j = 0; j < bits; j++)
+      for (i = j+1; i < bits; i++, n++)
+      {
+        table[n].syndrome   = bit_syndrome (j, bits) ^ bit_syndrome (i, bits);
+        table[n].error_bits = j | (i << 8);
+      }
+  return (n);
+}
+
+/**
+ * Check that a sorted `table` has no duplicate syndromes.
+ */
+static bool syndromes_unique (const syndrome_info *table, size_t num)
+{
+  size_t i;
+
+  for (i = 1; i < num; i++)
+      if (table[i].syndrome == table[i-1].syndrome)
+         return (false);
+  return (true);
+}
+
+/**
+ * Build the sorted syndrome tables once at startup.
+ */
+void modeS_init_syndromes (void)
+{
+  int i, n;
+
+  for (i = 0; i < MODES_SHORT_MSG_BITS; i++)
+  {
+    syndrome_short_1[i].syndrome   = bit_syndrome (i, MODES_SHORT_MSG_BITS);
+    syndrome_short_1[i].error_bits = i;
+  }
+  for (i = 0; i < MODES_LONG_MSG_BITS; i++)
   {
-    int byte1 = j / 8;
-    int bitmask1 = 1 << (7-(j % 8));
+    syndrome_long_1[i].syndrome   = bit_syndrome (i, MODES_LONG_MSG_BITS);
+    syndrome_long_1[i].error_bits = i;
+  }
 
-    /* Don't check the same pairs multiple times, so i starts from j+1 */
-    for (i = j+1; i < bits; i++)
-    {
-      int      byte2 = i / 8;
-      int      bitmask2 = 1 << (7-(i % 8));
-      uint32_t crc1, crc2;
+  n = init_two_bits_syndromes (syndrome_short_2, MODES_SHORT_MSG_BITS);
+  assert (n == DIM(syndrome_short_2));
+  n = init_two_bits_syndromes (syndrome_long_2, MODES_LONG_MSG_BITS);
+  assert (n == DIM(syndrome_long_2));
 
-      memcpy (aux, msg, bits/8);
+  qsort (syndrome_short_1, DIM(syndrome_short_1), sizeof(syndrome_short_1[0]), syndrome_compare);
+  qsort (syndrome_long_1, DIM(syndrome_long_1), sizeof(syndrome_long_1[0]), syndrome_compare);
+  qsort (syndrome_short_2, DIM(syndrome_short_2), sizeof(syndrome_short_2[0]), syndrome_compare);
+  qsort (syndrome_long_2, DIM(syndrome_long_2), sizeof(syndrome_long_2[0]), syndrome_compare);
 
-      aux[byte1] ^= bitmask1; /* Flip j-th bit. */
-      aux[byte2] ^= bitmask2; /* Flip i-th bit. */
+  /* A duplicate syndrome would silently flip the wrong bit(s).
+   */
+  syndromes_1_ok = (syndromes_unique(syndrome_short_1, DIM(syndrome_short_1)) &&
+                    syndromes_unique(syndrome_long_1, DIM(syndrome_long_1)));
+  syndromes_2_ok = (syndromes_unique(syndrome_short_2, DIM(syndrome_short_2)) &&
+                    syndromes_unique(syndrome_long_2, DIM(syndrome_long_2)));
+
+  if (!syndromes_1_ok)
+     LOG_STDERR ("Duplicate syndromes for 1 bit errors. Disabling the 1 bit error correction.\n");
+  if (!syndromes_2_ok)
+     LOG_STDERR ("Duplicate syndromes for 2 bit errors. Disabling the 2 bit error correction.\n");
+}
 
-      crc1 = ((uint32_t) aux [(bits/8)-3] << 16) |
-             ((uint32_t) aux [(bits/8)-2] << 8) |
-              (uint32_t) aux [(bits/8)-1];
-      crc2 = modeS_checksum (aux, bits);
+/**
+ * Lookup the syndrome of `msg` in a sorted `table`.
+ *
+ * \retval NULL if the CRC is okay or the syndrome is not in the table.
+ * \retval the matching entry otherwise.
+ */
+static const syndrome_info *syndrome_lookup (const uint8_t *msg, int bits, const syndrome_info *table, size_t num)
+{
+  syndrome_info key;
+  uint32_t      crc;
+
+  crc = ((uint32_t)msg[(bits/8)-3] << 16) |
+        ((uint32_t)msg[(bits/8)-2] << 8) |
+         (uint32_t)msg[(bits/8)-1];
+  key.syndrome = crc ^ modeS_checksum (msg, bits);
+  if (key.syndrome == 0)
+     return (NULL);
 
-      if (crc1 == crc2)
-      {
-        /* The error is fixed. Overwrite the original buffer with
-         * the corrected sequence, and returns the error bit
-         * position.
-         */
-        memcpy (msg, aux, bits/8);
+  return (bsearch(&key, table, num, sizeof(*table), syndrome_compare));
+}
 
-        /* We return the two bits as a 16 bit integer by shifting
-         * 'i' on the left. This is possible since 'i' will always
-         * be non-zero because i starts from j+1.
-         */
-        return (j | (i << 8));
-      }
-    }
-  }
-  return (-1);
+/**
+ * Try to fix single bit errors using the checksum. On success modifies
+ * the original buffer with the fixed version, and returns the position
+ * of the error bit. Otherwise if fixing failed, -1 is returned.
+ *
+ * Instead of flipping every bit and recomputing the checksum, the
+ * syndrome is looked up in a table built by `modeS_init_syndromes()`.
+ */
+int fix_single_bit_errors (uint8_t *msg, int bits)
+{
+  const syndrome_info *si;
+  int   i;
+
+  if (!syndromes_1_ok)
+     return (-1);
+
+  if (bits == MODES_LONG_MSG_BITS)
+       si = syndrome_lookup (msg, bits, syndrome_long_1, DIM(syndrome_long_1));
+  else si = syndrome_lookup (msg, bits, syndrome_short_1, DIM(syndrome_short_1));
+
+  if (!si)
+     return (-1);
+
+  i = si->error_bits;
+  msg [i / 8] ^= 1 << (7 - (i % 8));    /* Flip i-th bit. */
+  return (i);
+}
+
+/**
+ * Similar to `fix_single_bit_errors()` but for every possible two bit combination.
+ *
+ * A 2 bit error is more likely to be miscorrected and should be tried only
+ * against DF17 messages that don't pass the checksum, and only in Aggressive Mode.
+ */
+int fix_two_bits_errors (uint8_t *msg, int bits)
+{
+  const syndrome_info *si;
+  int   i, j;
+
+  if (!syndromes_2_ok)
+     return (-1);
+
+  if (bits == MODES_LONG_MSG_BITS)
+       si = syndrome_lookup (msg, bits, syndrome_long_2, DIM(syndrome_long_2));
+  else si = syndrome_lookup (msg, bits, syndrome_short_2, DIM(syndrome_short_2));
+  if (!si)
+     return (-1);
+
+  j = si->error_bits & 0xFF;
+  i = si->error_bits >> 8;
+  msg [j / 8] ^= 1 << (7 - (j % 8));    /* Flip j-th bit. */
+  msg [i / 8] ^= 1 << (7 - (i % 8));    /* Flip i-th bit. */
+  return (si->error_bits);
 }
 
 /**
@@ -1863,58 +2285,376 @@ const char *get_ME_description (const modeS_message *mm)
 }
 
 /**
- * Decode a raw Mode S message demodulated as a stream of bytes by `detect_modeS()`.
- *
- * And split it into fields populating a `modeS_message` structure.
+ * Return the Navigation Integrity Category for a position message.
+ * The NIC follows the ME type, with the NIC supplement-A for the ME types
+ * having 2 possible values. The NIC supplement-B (in ME type 29) is not used.
  */
-void decode_modeS_message (modeS_message *mm, const uint8_t *_msg)
+int get_NIC (int ME_type, bool NIC_supp_A)
 {
-  uint32_t    crc2;   /* Computed CRC, used to verify the message CRC. */
-  const char *AIS_charset = "?ABCDEFGHIJKLMNOPQRSTUVWXYZ????? ???????????????0123456789??????";
-  uint8_t    *msg;
-
-  memset (mm, '\0', sizeof(*mm));
+  static const int NIC_by_type [23] = {
+                   -1, -1, -1, -1, -1,              /* ME type 0 - 4 */
+                   11, 10,  8,  0,                  /* ME type 5 - 8, surface */
+                   11, 10,  8,  7,  6,  5,  4,  2,  1,  0,  /* ME type 9 - 18, baro altitude */
+                   -1, 11, 10,  0                   /* ME type 20 - 22, GNSS height */
+                 };
+  int NIC;
+
+  if (ME_type < 0 || ME_type >= DIM(NIC_by_type))
+     return (-1);
 
-  /* Work on our local copy
-   */
-  memcpy (mm->msg, _msg, sizeof(mm->msg));
-  msg = mm->msg;
+  NIC = NIC_by_type [ME_type];
+  if (NIC_supp_A && (ME_type == 7 || ME_type == 11 || ME_type == 16))
+     NIC++;
+  return (NIC);
+}
 
-  /* Get the message type ASAP as other operations depend on this
-   */
-  mm->msg_type = msg[0] >> 3;    /* Downlink Format */
-  mm->msg_bits = modeS_message_len_by_type (mm->msg_type);
+/**
+ * Return the bits `first` to `last` (counting from 1 as in ICAO Annex 10)
+ * of the 56 bits MB field in a Comm-B reply (or the ME field in DF17). At most 32 bits.
+ */
+static uint32_t MB_bits (const uint8_t *mb, int first, int last)
+{
+  uint32_t val = 0;
+  int      i;
 
-  /* CRC is always the last three bytes.
-   */
-  mm->CRC = ((uint32_t)msg[(mm->msg_bits/8)-3] << 16) |
-            ((uint32_t)msg[(mm->msg_bits/8)-2] << 8) |
-             (uint32_t)msg[(mm->msg_bits/8)-1];
-  crc2 = modeS_checksum (msg, mm->msg_bits);
+  for (i = first - 1; i < last; i++)
+      val = (val << 1) | ((mb [i / 8] >> (7 - (i % 8))) & 1);
+  return (val);
+}
 
-  /* Check CRC and fix single bit errors using the CRC when
-   * possible (DF 11 and 17).
-   */
-  mm->error_bit = -1;    /* No error */
-  mm->CRC_ok = (mm->CRC == crc2);
+/**
+ * As `MB_bits()`, but for a 2's complement value with the sign bit
+ * just before `first`.
+ */
+static int MB_signed (const uint8_t *mb, int first, int last)
+{
+  int val = (int) MB_bits (mb, first, last);
 
-  if (!mm->CRC_ok && (mm->msg_type == 11 || mm->msg_type == 17))
-  {
-    mm->error_bit = fix_single_bit_errors (msg, mm->msg_bits);
-    if (mm->error_bit != -1)
-    {
-      mm->CRC = modeS_checksum (msg, mm->msg_bits);
-      mm->CRC_ok = true;
-    }
-    else if (Modes.aggressive && mm->msg_type == 17 && (mm->error_bit = fix_two_bits_errors(msg, mm->msg_bits)) != -1)
-    {
-      mm->CRC = modeS_checksum (msg, mm->msg_bits);
-      mm->CRC_ok = true;
-    }
-  }
+  if (MB_bits(mb, first - 1, first - 1))
+     val -= 1 << (last - first + 1);
+  return (val);
+}
 
-  /* Note: most of the other computation happens **after** we fix the single bit errors.
-   * Otherwise we would need to recompute the fields again.
+/**
+ * The BDS 1,7 "Common usage GICB capability report".
+ * All reserved bits 25 - 56 must be 0 and BDS 2,0 must be supported.
+ */
+static bool is_BDS_17 (const uint8_t *mb)
+{
+  return (MB_bits(mb, 25, 56) == 0 && MB_bits(mb, 7, 7) == 1);
+}
+
+/**
+ * The BDS 2,0 "Aircraft identification".
+ */
+static bool is_BDS_20 (const uint8_t *mb, const char *AIS_charset)
+{
+  int i;
+
+  if (MB_bits(mb, 1, 8) != 0x20)
+     return (false);
+
+  for (i = 0; i < 8; i++)
+      if (AIS_charset [MB_bits(mb, 9 + 6*i, 14 + 6*i)] == '?')
+         return (false);
+  return (true);
+}
+
+/**
+ * The BDS 4,0 "Selected vertical intention".
+ * A value with the status bit cleared must be 0.
+ */
+static bool is_BDS_40 (const uint8_t *mb)
+{
+  if (MB_bits(mb, 40, 47) != 0 || MB_bits(mb, 52, 53) != 0)
+     return (false);
+
+  if (!MB_bits(mb, 1, 1) || MB_bits(mb, 2, 13) * 16 > 50000)
+     return (false);
+
+  if (!MB_bits(mb, 14, 14) && MB_bits(mb, 15, 26) != 0)
+     return (false);
+
+  if (MB_bits(mb, 27, 27))
+  {
+    double baro = 800.0 + 0.1 * MB_bits (mb, 28, 39);
+
+    if (baro < 900.0 || baro > 1100.0)
+       return (false);
+  }
+  else if (MB_bits(mb, 28, 39) != 0)
+     return (false);
+  return (true);
+}
+
+/**
+ * The BDS 5,0 "Track and turn report".
+ */
+static bool is_BDS_50 (const uint8_t *mb)
+{
+  int gs, tas;
+
+  if (!MB_bits(mb, 1, 1) || !MB_bits(mb, 12, 12) || !MB_bits(mb, 24, 24))
+     return (false);
+
+  if (abs(MB_signed(mb, 3, 11)) * 45.0 / 256.0 > 50.0)
+     return (false);
+
+  gs = 2 * MB_bits (mb, 25, 34);
+  if (gs > 600)
+     return (false);
+
+  if (!MB_bits(mb, 35, 35) && MB_bits(mb, 36, 45) != 0)
+     return (false);
+
+  if (MB_bits(mb, 46, 46))
+  {
+    tas = 2 * MB_bits (mb, 47, 56);
+    if (tas > 600 || abs(gs - tas) > 200)
+       return (false);
+  }
+  else if (MB_bits(mb, 47, 56) != 0)
+     return (false);
+  return (true);
+}
+
+/**
+ * The BDS 6,0 "Heading and speed report".
+ */
+static bool is_BDS_60 (const uint8_t *mb)
+{
+  int    IAS;
+  double mach;
+
+  if (!MB_bits(mb, 1, 1) || !MB_bits(mb, 13, 13) || !MB_bits(mb, 24, 24))
+     return (false);
+
+  IAS  = MB_bits (mb, 14, 23);
+  mach = 2.048 * MB_bits (mb, 25, 34) / 512.0;
+  if (IAS == 0 || IAS > 500 || mach > 1.0)
+     return (false);
+
+  if (MB_bits(mb, 35, 35))
+  {
+    if (abs(32 * MB_signed(mb, 37, 45)) > 6000)
+       return (false);
+  }
+  else if (MB_bits(mb, 36, 45) != 0)
+     return (false);
+
+  if (MB_bits(mb, 46, 46))
+  {
+    if (abs(32 * MB_signed(mb, 48, 56)) > 6000)
+       return (false);
+  }
+  else if (MB_bits(mb, 47, 56) != 0)
+     return (false);
+  return (true);
+}
+
+/**
+ * Decode the MB field of a DF20 or DF21 Comm-B reply.
+ *
+ * The register number (BDS) is not part of the reply. Hence guess it from
+ * the registers BDS 1,7, 2,0, 4,0, 5,0 and 6,0 by checking the reserved
+ * bits, status bits and the range of values. If none or more than one
+ * register matches, `mm->BDS` is left at 0.
+ */
+void decode_comm_B (modeS_message *mm, const char *AIS_charset)
+{
+  const uint8_t *mb = mm->msg + 4;
+  int   matches = 0;
+  int   i;
+
+  if (MB_bits(mb, 1, 28) == 0 && MB_bits(mb, 29, 56) == 0)
+     return;
+
+  if (is_BDS_17(mb))
+  {
+    mm->BDS = 0x17;
+    matches++;
+  }
+  if (is_BDS_20(mb, AIS_charset))
+  {
+    mm->BDS = 0x20;
+    matches++;
+  }
+  if (is_BDS_40(mb))
+  {
+    mm->BDS = 0x40;
+    matches++;
+  }
+  if (is_BDS_50(mb))
+  {
+    mm->BDS = 0x50;
+    matches++;
+  }
+  if (is_BDS_60(mb))
+  {
+    mm->BDS = 0x60;
+    matches++;
+  }
+
+  if (matches != 1)
+  {
+    mm->BDS = 0;
+    return;
+  }
+
+  switch (mm->BDS)
+  {
+    case 0x17:
+         mm->BDS_capability = MB_bits (mb, 1, 24);
+         break;
+
+    case 0x20:
+         for (i = 0; i < 8; i++)
+             mm->flight [i] = AIS_charset [MB_bits(mb, 9 + 6*i, 14 + 6*i)];
+         mm->flight [8] = '\0';
+         break;
+
+    case 0x40:
+         mm->sel_altitude = 16 * MB_bits (mb, 2, 13);
+         if (MB_bits(mb, 27, 27))
+            mm->baro_setting = 800.0 + 0.1 * MB_bits (mb, 28, 39);
+         break;
+
+    case 0x50:
+         mm->roll         = 45.0 * MB_signed (mb, 3, 11) / 256.0;
+         mm->true_track   = 90.0 * MB_signed (mb, 14, 23) / 512.0;
+         mm->ground_speed = 2 * MB_bits (mb, 25, 34);
+         if (mm->true_track < 0)
+            mm->true_track += 360.0;
+         if (MB_bits(mb, 46, 46))
+            mm->true_airspeed = 2 * MB_bits (mb, 47, 56);
+         break;
+
+    case 0x60:
+         mm->mag_heading = 90.0 * MB_signed (mb, 3, 12) / 512.0;
+         mm->IAS         = MB_bits (mb, 14, 23);
+         mm->mach        = 2.048 * MB_bits (mb, 25, 34) / 512.0;
+         if (mm->mag_heading < 0)
+            mm->mag_heading += 360.0;
+         if (MB_bits(mb, 35, 35))
+            mm->baro_vert_rate = 32 * MB_signed (mb, 37, 45);
+         break;
+  }
+}
+
+/**
+ * Print the Comm-B fields decoded by `decode_comm_B()`.
+ */
+void display_comm_B (const modeS_message *mm)
+{
+  static const char *GICB_regs[24] = {
+                     "0,5", "0,6", "0,7", "0,8", "0,9", "0,A", "2,0", "2,1",
+                     "4,0", "4,1", "4,2", "4,3", "4,4", "4,5", "4,8", "5,0",
+                     "5,1", "5,2", "5,3", "5,4", "5,5", "5,6", "5,F", "6,0"
+                   };
+  char caps [4 * DIM(GICB_regs) + 1];
+  int  i;
+
+  if (mm->BDS == 0)
+  {
+    LOG_STDOUT ("  Comm-B         : unknown BDS register\n");
+    return;
+  }
+
+  LOG_STDOUT ("  Comm-B         : BDS %d,%d\n", mm->BDS >> 4, mm->BDS & 0xF);
+
+  switch (mm->BDS)
+  {
+    case 0x17:
+         caps[0] = '\0';
+         for (i = 0; i < 24; i++)
+             if (mm->BDS_capability & (1 << (23 - i)))
+             {
+               strcat (caps, " ");
+               strcat (caps, GICB_regs[i]);
+             }
+         LOG_STDOUT ("    Capability   :%s\n", caps[0] ? caps : " none");
+         break;
+
+    case 0x20:
+         LOG_STDOUT ("    Identification: %s\n", mm->flight);
+         break;
+
+    case 0x40:
+         LOG_STDOUT ("    Selected Alt : %d feet\n", mm->sel_altitude);
+         if (mm->baro_setting > 0)
+            LOG_STDOUT ("    Baro setting : %.1f mb\n", mm->baro_setting);
+         break;
+
+    case 0x50:
+         LOG_STDOUT ("    Roll         : %.1f deg\n", mm->roll);
+         LOG_STDOUT ("    True track   : %.1f deg\n", mm->true_track);
+         LOG_STDOUT ("    Ground speed : %d kts\n", mm->ground_speed);
+         if (mm->true_airspeed > 0)
+            LOG_STDOUT ("    True airspeed: %d kts\n", mm->true_airspeed);
+         break;
+
+    case 0x60:
+         LOG_STDOUT ("    Mag heading  : %.1f deg\n", mm->mag_heading);
+         LOG_STDOUT ("    IAS          : %d kts\n", mm->IAS);
+         LOG_STDOUT ("    Mach         : %.3f\n", mm->mach);
+         LOG_STDOUT ("    Baro rate    : %d ft/min\n", mm->baro_vert_rate);
+         break;
+  }
+}
+
+/**
+ * Decode a raw Mode S message demodulated as a stream of bytes by `detect_modeS()`.
+ *
+ * And split it into fields populating a `modeS_message` structure.
+ */
+void decode_modeS_message (modeS_message *mm, const uint8_t *_msg)
+{
+  uint32_t    crc2;   /* Computed CRC, used to verify the message CRC. */
+  const char *AIS_charset = "?ABCDEFGHIJKLMNOPQRSTUVWXYZ????? ???????????????0123456789??????";
+  uint8_t    *msg;
+
+  memset (mm, '\0', sizeof(*mm));
+
+  /* Work on our local copy
+   */
+  memcpy (mm->msg, _msg, sizeof(mm->msg));
+  msg = mm->msg;
+
+  /* Get the message type ASAP as other operations depend on this
+   */
+  mm->msg_type = msg[0] >> 3;    /* Downlink Format */
+  mm->msg_bits = modeS_message_len_by_type (mm->msg_type);
+
+  /* CRC is always the last three bytes.
+   */
+  mm->CRC = ((uint32_t)msg[(mm->msg_bits/8)-3] << 16) |
+            ((uint32_t)msg[(mm->msg_bits/8)-2] << 8) |
+             (uint32_t)msg[(mm->msg_bits/8)-1];
+  crc2 = modeS_checksum (msg, mm->msg_bits);
+
+  /* Check CRC and fix single bit errors using the CRC when
+   * possible (DF 11 and 17).
+   */
+  mm->error_bit = -1;    /* No error */
+  mm->CRC_ok = (mm->CRC == crc2);
+
+  if (!mm->CRC_ok && (mm->msg_type == 11 || mm->msg_type == 17))
+  {
+    mm->error_bit = fix_single_bit_errors (msg, mm->msg_bits);
+    if (mm->error_bit != -1)
+    {
+      mm->CRC = modeS_checksum (msg, mm->msg_bits);
+      mm->CRC_ok = true;
+    }
+    else if (Modes.aggressive && mm->msg_type == 17 && (mm->error_bit = fix_two_bits_errors(msg, mm->msg_bits)) != -1)
+    {
+      mm->CRC = modeS_checksum (msg, mm->msg_bits);
+      mm->CRC_ok = true;
+    }
+  }
+
+  /* Note: most of the other computation happens **after** we fix the single bit errors.
+   * Otherwise we would need to recompute the fields again.
    */
   mm->ca = msg[0] & 7;        /* Responder capabilities. */
 
@@ -2003,6 +2743,11 @@ void decode_modeS_message (modeS_message *mm, const uint8_t *_msg)
   if (mm->msg_type == 0 || mm->msg_type == 4 || mm->msg_type == 16 || mm->msg_type == 20)
      mm->altitude = decode_AC13_field (msg, &mm->unit);
 
+  /* Guess the register of the 56 bits MB field in DF20 and DF21.
+   */
+  if (mm->msg_type == 20 || mm->msg_type == 21)
+     decode_comm_B (mm, AIS_charset);
+
   /** Decode extended squitter specific stuff.
    */
   if (mm->msg_type == 17)
@@ -2024,6 +2769,15 @@ void decode_modeS_message (modeS_message *mm, const uint8_t *_msg)
       mm->flight[7] = AIS_charset [msg[10] & 63];
       mm->flight[8] = '\0';
     }
+    else if (mm->ME_type >= 5 && mm->ME_type <= 8)
+    {
+      /* Surface position Message. No altitude.
+       */
+      mm->odd_flag = msg[6] & (1 << 2);
+      mm->UTC_flag = msg[6] & (1 << 3);
+      mm->raw_latitude  = ((msg[6] & 3) << 15) | (msg[7] << 7) | (msg[8] >> 1); /* Bits 23 - 39 */
+      mm->raw_longitude = ((msg[8] & 1) << 16) | (msg[9] << 8) | msg[10];       /* Bits 40 - 56 */
+    }
     else if (mm->ME_type >= 9 && mm->ME_type <= 18)
     {
       /* Airborne position Message
@@ -2083,6 +2837,20 @@ void decode_modeS_message (modeS_message *mm, const uint8_t *_msg)
         mm->heading = (int) (360.0/128) * (((msg[5] & 3) << 5) | (msg[6] >> 3));
       }
     }
+    else if (mm->ME_type == 31 && (mm->ME_subtype == 0 || mm->ME_subtype == 1))
+    {
+      /* Aircraft Operational Status. Bits 41 - 52 of the ME field
+       * are the same in version 1 and 2. In version 0 and the reserved
+       * versions 3 - 7, only the version number is defined.
+       */
+      mm->ADSB_version = MB_bits (msg + 4, 41, 43);
+      if (mm->ADSB_version == 1 || mm->ADSB_version == 2)
+      {
+        mm->NIC_supp_A = MB_bits (msg + 4, 44, 44);
+        mm->NACp       = MB_bits (msg + 4, 45, 48);
+        mm->SIL        = MB_bits (msg + 4, 51, 52);
+      }
+    }
   }
   mm->phase_corrected = false;  /* Set to 'true' by the caller if needed. */
 }
@@ -2173,9 +2941,7 @@ void display_modeS_message (const modeS_message *mm)
     LOG_STDOUT ("  ICAO Address   : %s\n", get_ICAO_details(mm->AA1, mm->AA2, mm->AA3));
 
     if (mm->msg_type == 20)
-    {
-      /** \todo 56 bits DF20 MB additional field. */
-    }
+       display_comm_B (mm);
   }
   else if (mm->msg_type == 5 || mm->msg_type == 21)
   {
@@ -2187,9 +2953,7 @@ void display_modeS_message (const modeS_message *mm)
     LOG_STDOUT ("  ICAO Address   : %s\n", get_ICAO_details(mm->AA1, mm->AA2, mm->AA3));
 
     if (mm->msg_type == 21)
-    {
-      /** \todo 56 bits DF21 MB additional field. */
-    }
+       display_comm_B (mm);
   }
   else if (mm->msg_type == 11)
   {
@@ -2221,6 +2985,13 @@ void display_modeS_message (const modeS_message *mm)
       LOG_STDOUT ("    Aircraft Type  : %s\n", ac_type_str[mm->aircraft_type]);
       LOG_STDOUT ("    Identification : %s\n", mm->flight);
     }
+    else if (mm->ME_type >= 5 && mm->ME_type <= 8)
+    {
+      LOG_STDOUT ("    F flag   : %s\n", mm->odd_flag ? "odd" : "even");
+      LOG_STDOUT ("    T flag   : %s\n", mm->UTC_flag ? "UTC" : "non-UTC");
+      LOG_STDOUT ("    Latitude : %d (not decoded)\n", mm->raw_latitude);
+      LOG_STDOUT ("    Longitude: %d (not decoded)\n", mm->raw_longitude);
+    }
     else if (mm->ME_type >= 9 && mm->ME_type <= 18)
     {
       LOG_STDOUT ("    F flag   : %s\n", mm->odd_flag ? "odd" : "even");
@@ -2264,15 +3035,21 @@ void display_modeS_message (const modeS_message *mm)
       else
         LOG_STDOUT ("    Unrecognized ME subtype: %d\n", mm->ME_subtype);
     }
+    else if (mm->ME_type == 31 && (mm->ME_subtype == 0 || mm->ME_subtype == 1))  /* Aircraft operation status */
+    {
+      LOG_STDOUT ("    ADS-B version  : %d\n", mm->ADSB_version);
+      if (mm->ADSB_version == 1 || mm->ADSB_version == 2)
+      {
+        LOG_STDOUT ("    NIC supplement : %d\n", mm->NIC_supp_A);
+        LOG_STDOUT ("    NACp           : %d\n", mm->NACp);
+        LOG_STDOUT ("    SIL            : %d\n", mm->SIL);
+      }
+    }
 #if 0
     /**\todo */
     else if (mm->ME_type == 29)  /* Target State + Status Message */
     {
     }
-    /**\todo Ref: chapter 8 in `The-1090MHz-riddle.pdf` */
-    else if (mm->ME_type == 31)  /* Aircraft operation status */
-    {
-    }
 #endif
     else
     {
@@ -2596,22 +3373,31 @@ good_preamble:
       }
       mm.sig_level = signal_power / (65536.0 * signal_len);
 
+      /* The MLAT timestamp counts at 12 MHz from the first sample.
+       */
+      mm.timestamp_msg = (uint64_t) ((double) (Modes.sample_counter + j) * 12E6 / Modes.sample_rate);
+
       /* Update statistics.
        */
       if (mm.CRC_ok || use_correction)
       {
         if (errors == 0)
            Modes.stat.demodulated++;
+        Modes.stat.DF_total [mm.msg_type]++;
         if (mm.error_bit == -1)
         {
           if (mm.CRC_ok)
-               Modes.stat.good_CRC++;
+          {
+            Modes.stat.good_CRC++;
+            Modes.stat.DF_CRC_ok [mm.msg_type]++;
+          }
           else Modes.stat.bad_CRC++;
         }
         else
         {
           Modes.stat.bad_CRC++;
           Modes.stat.fixed++;
+          Modes.stat.DF_fixed [mm.msg_type]++;
           if (mm.error_bit < MODES_LONG_MSG_BITS)
                Modes.stat.single_bit_fix++;
           else Modes.stat.two_bits_fix++;
@@ -2670,6 +3456,115 @@ good_preamble:
   return (rc);
 }
 
+/*
+ * The sample of Mode A/C pulse `k` and of the gap after it;
+ * relative to the F1 framing pulse.
+ */
+#define AC_PULSE(k)  ((29*(k) + 5) / 10)
+#define AC_GAP(k)    ((29*(k) + 19) / 10)
+
+/**
+ * Detect Mode A/C replies inside the magnitude buffer pointed by `m` and of
+ * size `mlen` samples. Only called with option `--modeac`.
+ *
+ * A Mode A/C reply has 2 framing pulses (F1 and F2) 20.3 usec apart and
+ * up to 13 information pulses between them. Each pulse is 0.45 usec
+ * wide and they are 1.45 usec apart:
+ * ```
+ *  F1 C1 A1 C2 A2 C4 A4 X B1 D1 B2 D2 B4 D4 F2
+ * ```
+ *
+ * If we are sampling at 2 MHz, pulse `k` starts at sample `2.9 * k`.
+ * Every gap between the pulses must be low. This rejects most Mode S
+ * replies since these have pulses every 0.5 or 1 usec.
+ *
+ * A reply to a Mode A or Mode C interrogation can not be told apart.
+ * Hence it is always taken as a Mode A squawk.
+ *
+ * \retval the number of Mode A/C replies found.
+ */
+int detect_modeAC (const uint16_t *m, uint32_t mlen)
+{
+  uint32_t j;
+  int      num = 0;
+
+  for (j = 1; j < mlen - 2*MODES_FULL_LEN; j++)
+  {
+    uint32_t F1 = m [j];
+    uint32_t F2 = m [j + AC_PULSE(14)];
+    uint32_t high;
+    bool     p [14];
+    int      a, b, c, d, k;
+
+    if (Modes.exit)
+       break;
+
+    /* The framing pulses must be strong and of similar level. And
+     * there must be no energy just before F1.
+     */
+    if (F1 < MODES_AC_MIN_LEVEL || F2 < MODES_AC_MIN_LEVEL || F1 > 2*F2 || F2 > 2*F1)
+       continue;
+
+    high = (F1 + F2) / 4;
+    if (m[j-1] >= high)
+       continue;
+
+    for (k = 0; k < 14; k++)
+    {
+      if (m[j + AC_GAP(k)] >= high)
+         break;
+      p [k] = (k == 0 || m[j + AC_PULSE(k)] >= high);
+    }
+    if (k < 14)
+       continue;    /* Energy in a gap; not a Mode A/C reply */
+
+    /* The X pulse is never set. And a reply without any code
+     * pulses is most likely noise.
+     */
+    a = 4*p[6]  + 2*p[4]  + p[2];
+    b = 4*p[12] + 2*p[10] + p[8];
+    c = 4*p[5]  + 2*p[3]  + p[1];
+    d = 4*p[13] + 2*p[11] + p[9];
+    if (p[7] || (a | b | c | d) == 0)
+       continue;
+
+    modeAC_user_reply (a, b, c, d);
+    num++;
+    j += AC_PULSE (14);
+  }
+  return (num);
+}
+
+/**
+ * Count a Mode A/C reply found by `detect_modeAC()` and correlate it with
+ * the squawks of the Mode S aircrafts. Then send it to the raw output
+ * as a 2 byte `*ABCD;` message (like the `--modeac` option of other
+ * dump1090 programs).
+ */
+void modeAC_user_reply (int a, int b, int c, int d)
+{
+  const aircraft *ac;
+  int             identity = a*1000 + b*100 + c*10 + d;
+
+  Modes.stat.AC_replies++;
+  Modes.stat.AC_squawks [(a << 9) | (b << 6) | (c << 3) | d]++;
+
+  for (ac = Modes.aircrafts; ac; ac = ac->next)
+      if (ac->identity == identity)
+      {
+        Modes.stat.AC_matched++;
+        break;
+      }
+
+  if (Modes.net && handler_sending(MODES_NET_SERVICE_RAW_OUT))
+  {
+    char msg [10];
+    int  len = snprintf (msg, sizeof(msg), "*%02X%02X;\n", (a << 4) | b, (c << 4) | d);
+
+    connection_send (MODES_NET_SERVICE_RAW_OUT, msg, len);
+  }
+}
+
 /**
  * When a new message is available, because it was decoded from the
  * RTL/SDRplay device, file, or received in a TCP input port, or any other
@@ -2687,6 +3582,8 @@ void modeS_user_message (const modeS_message *mm)
      return;
 
   Modes.stat.messages_total++;
+  if (Modes.rate_csv)
+     rate_update (mm);
 
 
   /* Track aircrafts in interactive mode or if we have some HTTP / SBS clients.
@@ -2694,7 +3591,7 @@ void modeS_user_message (const modeS_message *mm)
   num_clients = Modes.stat.cli_accepted [MODES_NET_SERVICE_HTTP] +
                 Modes.stat.cli_accepted [MODES_NET_SERVICE_SBS_OUT];
 
-  if (Modes.interactive || num_clients > 0)
+  if (Modes.interactive || num_clients > 0 || Modes.pos_report)
   {
     uint64_t  now = MSEC_TIME();
     aircraft *a = interactive_receive_data (mm, now);
@@ -2735,6 +3632,7 @@ void modeS_user_message (const modeS_message *mm)
 aircraft *aircraft_create (uint32_t addr, uint64_t now)
 {
   aircraft *a = calloc (sizeof(*a), 1);
+  uint64_t  num;
 
   if (a)
   {
@@ -2743,12 +3641,19 @@ aircraft *aircraft_create (uint32_t addr, uint64_t now)
     a->seen_last  = now;
     a->CSV        = aircraft_CSV_lookup_entry (addr);
     a->show       = A_SHOW_FIRST_TIME;
+    a->NIC        = -1;
 
     /* We really can't tell if it's unique since we keep no global list of that yet
      */
     Modes.stat.unique_aircrafts++;
     if (a->CSV)
        Modes.stat.unique_aircrafts_CSV++;
+
+    /* The caller adds it to `Modes.aircrafts`. Hence the `+ 1`.
+     */
+    num = (uint64_t) aircraft_numbers() + 1;
+    if (num > Modes.stat.peak_aircrafts)
+       Modes.stat.peak_aircrafts = num;
   }
   return (a);
 }
@@ -2812,8 +3717,48 @@ double great_circle_dist (pos_t pos1, pos_t pos2)
   return (6371000.0 * acos(angle));
 }
 
+/**
+ * Initial bearing (in degrees, 0 - 360) from `pos1` to `pos2` on a spherical earth.
+ *
+ * \ref https://www.movable-type.co.uk/scripts/latlong.html
+ */
+double great_circle_bearing (pos_t pos1, pos_t pos2)
+{
+  double lat1 = TWO_PI * pos1.lat / 360.0;  /* convert to radians */
+  double lat2 = TWO_PI * pos2.lat / 360.0;
+  double dlon = TWO_PI * (pos2.lon - pos1.lon) / 360.0;
+  double bearing;
+
+  bearing = atan2 (sin(dlon) * cos(lat2),
+                   cos(lat1) * sin(lat2) - sin(lat1) * cos(lat2) * cos(dlon));
+  bearing = 360.0 * bearing / TWO_PI;
+  if (bearing < 0.0)
+     bearing += 360.0;
+  return (bearing);
+}
+
+/**
+ * Update the actual range outline with this aircraft's position.
+ * Keep the position furthest away from home in each bearing sector.
+ */
+void set_range_outline (const aircraft *a)
+{
+  range_point *rp;
+  double       bearing = great_circle_bearing (Modes.home_pos, a->position);
+  int          sector  = (int) (bearing * MODES_RANGE_BEARINGS / 360.0);
+
+  rp = &Modes.range_outline [sector % MODES_RANGE_BEARINGS];
+  if (a->distance > rp->distance)
+  {
+    rp->pos      = a->position;
+    rp->distance = a->distance;
+    rp->altitude = a->altitude;
+  }
+}
+
 /**
  * Set this aircraft's distance to our home position.
+ * And update the actual range outline.
  *
  * The reference time-tick is the latest of `a->odd_CPR_time` and `a->even_CPR_time`.
  */
@@ -2824,7 +3769,12 @@ void set_home_distance (aircraft *a)
     double distance = great_circle_dist (a->position, Modes.home_pos);
 
     if (distance != 0.0)
-       a->distance = distance;
+    {
+      a->distance = distance;
+      if (distance > Modes.stat.max_range)
+         Modes.stat.max_range = distance;
+      set_range_outline (a);
+    }
     a->EST_position  = a->position;
     a->EST_seen_last = (a->even_CPR_time > a->odd_CPR_time) ? a->even_CPR_time : a->odd_CPR_time;
   }
@@ -3082,25 +4032,158 @@ double CPR_Dlong_func (double lat, int isodd)
 }
 
 /**
- * Decode the **CPR** (*Compact Position Reporting*).
- *
- * This algorithm comes from: <br>
- * http://www.lll.lu/~edward/edward/adsb/DecodingADSBposition.html.
- *
- * A few remarks:
- *
- * \li 131072 is 2^17 since CPR latitude and longitude are encoded in 17 bits.
- * \li We assume that we always received the odd packet as last packet for
- *     simplicity. This may provide a position that is less fresh of a few seconds.
+ * Return the name of a `a_suspect_t` reason. As used in the JSON-data.
  */
-void decode_CPR (aircraft *a)
+const char *aircraft_suspect_name (a_suspect_t reason)
 {
-  const double AirDlat0 = 360.0 / 60;
-  const double AirDlat1 = 360.0 / 59;
-  double lat0 = a->even_CPR_lat;
-  double lat1 = a->odd_CPR_lat;
-  double lon0 = a->even_CPR_lon;
-  double lon1 = a->odd_CPR_lon;
+  return (reason == A_SUSPECT_JUMPS     ? "jumps"     :
+          reason == A_SUSPECT_DUPLICATE ? "duplicate" :
+          reason == A_SUSPECT_TRACK     ? "track"     : "");
+}
+
+/**
+ * Mark the aircraft as suspect for `reason`, unless it already is.
+ */
+void aircraft_set_suspect (aircraft *a, a_suspect_t reason)
+{
+  static const char *why[] = { "",
+                               "Implausible positions in a row",
+                               "The rejected positions form a second track",
+                               "The track does not match the heading"
+                             };
+  if (a->suspect)
+     return;
+
+  LOG_FILEONLY ("%06X: %s. Ghost or duplicate ICAO address?\n", a->addr, why[reason]);
+  a->suspect = reason;
+  Modes.stat.suspect_aircrafts++;
+}
+
+/**
+ * Write a position rejected by a range or speed check to the `--pos-report` file.
+ * With `distance` and the `limit` it exceeded (in meters).
+ */
+void position_rejected (const aircraft *a, pos_t pos, const char *reason, double distance, double limit)
+{
+  if (!Modes.pos_report)
+     return;
+
+  fprintf (Modes.pos_report, "%.3f,%06X,%.6f,%.6f,%s,%.1f,%.1f\n",
+           (double)a->msg_time / 1000.0, a->addr, pos.lat, pos.lon, reason,
+           distance / 1000.0, limit / 1000.0);
+  Modes.pos_report_num++;
+}
+
+/**
+ * Check if the aircraft could have moved from its last position to `pos`
+ * since `a->position_time`. With `a->msg_time` as the time of the new position.
+ *
+ * A position needing a speed above `MODES_MAX_SPEED` is rejected. Such jumps
+ * are caused by bad CPR decodes, decoding artifacts or 2 aircrafts using the
+ * same ICAO address. The aircraft is marked as suspect when:
+ *  \li `MODES_MAX_BAD_POSITIONS` positions are rejected in a row. The new position is then accepted.
+ *  \li a rejected position is plausible from the previous rejected position, with an
+ *      accepted position in between. I.e. the rejected positions alternate with the accepted
+ *      ones and form a second track. As 2 aircrafts with the same ICAO address would do.
+ *
+ * \retval true if `pos` is plausible.
+ */
+bool position_plausible (aircraft *a, pos_t pos)
+{
+  double distance, max_dist;
+  double sec = (double) (a->msg_time - a->position_time) / 1000.0;
+
+  if (VALID_POS(a->position) && a->position_time > 0)
+  {
+    /* Allow 500 m extra for the jitter in the reception times.
+     */
+    distance = great_circle_dist (pos, a->position);
+    max_dist = (MODES_MAX_SPEED * 1852.0 / 3600.0) * sec + 500.0;
+
+    if (distance > max_dist && ++a->bad_positions <= MODES_MAX_BAD_POSITIONS)
+    {
+      Modes.stat.CPR_speed_errors++;
+      position_rejected (a, pos, "speed", distance, max_dist);
+
+      if (a->bad_positions == 1 && VALID_POS(a->rejected_position) && a->rejected_time > 0)
+      {
+        sec      = (double) (a->msg_time - a->rejected_time) / 1000.0;
+        max_dist = (MODES_MAX_SPEED * 1852.0 / 3600.0) * sec + 500.0;
+        if (great_circle_dist(pos, a->rejected_position) <= max_dist)
+           aircraft_set_suspect (a, A_SUSPECT_DUPLICATE);
+      }
+      a->rejected_position = pos;
+      a->rejected_time     = a->msg_time;
+      return (false);
+    }
+    if (a->bad_positions > MODES_MAX_BAD_POSITIONS)
+    {
+      aircraft_set_suspect (a, A_SUSPECT_JUMPS);
+      a->rejected_position.lat = a->rejected_position.lon = 0.0;
+    }
+  }
+  a->bad_positions = 0;
+  a->position_time = a->msg_time;
+  track_plausible (a, pos);
+  return (true);
+}
+
+/**
+ * Compare the track from `a->track_position` to the accepted position `pos`
+ * against the heading reported in the velocity messages.
+ *
+ * The track is only taken over `MODES_TRACK_MIN_DIST` or more. Otherwise
+ * the errors in the positions would dominate. After `MODES_MAX_BAD_TRACKS`
+ * tracks in a row turning more than `MODES_MAX_TRACK_DIFF` degrees from
+ * the heading, the aircraft is marked as suspect.
+ */
+void track_plausible (aircraft *a, pos_t pos)
+{
+  double diff;
+
+  if (!a->heading_is_valid || !VALID_POS(a->track_position))
+  {
+    a->track_position = pos;
+    return;
+  }
+  if (great_circle_dist(pos, a->track_position) < MODES_TRACK_MIN_DIST)
+     return;
+
+  diff = fabs (great_circle_bearing(a->track_position, pos) - (double)a->heading);
+  if (diff > 180.0)
+     diff = 360.0 - diff;
+  a->track_position = pos;
+
+  if (diff <= MODES_MAX_TRACK_DIFF)
+     a->bad_tracks = 0;
+  else if (++a->bad_tracks >= MODES_MAX_BAD_TRACKS)
+     aircraft_set_suspect (a, A_SUSPECT_TRACK);
+}
+
+/**
+ * Decode the **CPR** (*Compact Position Reporting*).
+ *
+ * This algorithm comes from: <br>
+ * http://www.lll.lu/~edward/edward/adsb/DecodingADSBposition.html.
+ *
+ * A few remarks:
+ *
+ * \li 131072 is 2^17 since CPR latitude and longitude are encoded in 17 bits.
+ * \li We assume that we always received the odd packet as last packet for
+ *     simplicity. This may provide a position that is less fresh of a few seconds.
+ * \li If we have a home position, a result further away than `MODES_MAX_RANGE`
+ *     is taken as a bad decode and the old position is kept.
+ */
+void decode_CPR (aircraft *a)
+{
+  pos_t pos;
+
+  const double AirDlat0 = 360.0 / 60;
+  const double AirDlat1 = 360.0 / 59;
+  double lat0 = a->even_CPR_lat;
+  double lat1 = a->odd_CPR_lat;
+  double lon0 = a->even_CPR_lon;
+  double lon1 = a->odd_CPR_lon;
 
   /* Compute the Latitude Index "j"
    */
@@ -3127,8 +4210,8 @@ void decode_CPR (aircraft *a)
     int ni = CPR_N_func (rlat0, 0);
     int m = (int) floor ((((lon0 * (CPR_NL_func(rlat0)-1)) -
                          (lon1 * CPR_NL_func(rlat0))) / 131072) + 0.5);
-    a->position.lon = CPR_Dlong_func (rlat0, 0) * (CPR_mod_func(m, ni) + lon0/131072);
-    a->position.lat = rlat0;
+    pos.lon = CPR_Dlong_func (rlat0, 0) * (CPR_mod_func(m, ni) + lon0/131072);
+    pos.lat = rlat0;
   }
   else
   {
@@ -3136,13 +4219,113 @@ void decode_CPR (aircraft *a)
     int ni = CPR_N_func (rlat1, 1);
     int m  = (int) floor ((((lon0 * (CPR_NL_func(rlat1)-1)) -
                           (lon1 * CPR_NL_func(rlat1))) / 131072.0) + 0.5);
-    a->position.lon = CPR_Dlong_func (rlat1, 1) * (CPR_mod_func (m, ni) + lon1/131072);
-    a->position.lat = rlat1;
+    pos.lon = CPR_Dlong_func (rlat1, 1) * (CPR_mod_func (m, ni) + lon1/131072);
+    pos.lat = rlat1;
+  }
+
+  if (pos.lon > 180)
+     pos.lon -= 360;
+
+  if (Modes.home_pos_ok && great_circle_dist(pos, Modes.home_pos) > MODES_MAX_RANGE)
+  {
+    Modes.stat.CPR_range_errors++;
+    position_rejected (a, pos, "global-range", great_circle_dist(pos, Modes.home_pos), MODES_MAX_RANGE);
+    return;
+  }
+
+  if (!position_plausible(a, pos))
+     return;
+
+  Modes.stat.CPR_global_ok++;
+  a->position = pos;
+  set_home_distance (a);
+}
+
+/**
+ * Decode the **CPR** of a single message relative to a reference position. <br>
+ * This is the *locally unambiguous* decoding. The result is only correct if the
+ * aircraft is within half a CPR zone of `ref`. That is approx. 180 NM for an
+ * airborne position and 45 NM for a surface position.
+ *
+ * \param in  ref      the reference position.
+ * \param in  raw_lat  the 17 bit CPR latitude.
+ * \param in  raw_lon  the 17 bit CPR longitude.
+ * \param in  odd      true for an odd message.
+ * \param in  surface  true for a surface position message.
+ * \param out pos      the decoded position.
+ * \retval false if the decoded latitude is illegal.
+ */
+bool decode_CPR_relative (pos_t ref, int raw_lat, int raw_lon, bool odd, bool surface, pos_t *pos)
+{
+  double zone = surface ? 90.0 : 360.0;
+  double dlat = zone / (odd ? 59 : 60);
+  double lat  = raw_lat / 131072.0;
+  double lon  = raw_lon / 131072.0;
+  double dlon, rlat, rlon;
+  int    j, m;
+
+  /* The latitude and longitude index are those closest to `ref`.
+   */
+  j    = (int) floor (ref.lat / dlat) + (int) floor (0.5 + ref.lat / dlat - floor(ref.lat / dlat) - lat);
+  rlat = dlat * (j + lat);
+  if (rlat < -90.0 || rlat > 90.0)
+     return (false);
+
+  dlon = zone / CPR_N_func (rlat, odd ? 1 : 0);
+  m    = (int) floor (ref.lon / dlon) + (int) floor (0.5 + ref.lon / dlon - floor(ref.lon / dlon) - lon);
+  rlon = dlon * (m + lon);
+
+  if (rlon > 180.0)
+     rlon -= 360.0;
+  else if (rlon < -180.0)
+     rlon += 360.0;
+
+  pos->lat = rlat;
+  pos->lon = rlon;
+  return (true);
+}
+
+/**
+ * Decode the **CPR** of a single message relative to our home position.
+ *
+ * Used for the first airborne position before we have an odd and even message
+ * for a global decode. And for all surface positions since these can not be
+ * decoded globally without a reference position anyway.
+ *
+ * \param in a        the aircraft to set the position for.
+ * \param in mm       the message with the CPR data.
+ * \param in surface  true for a surface position message.
+ */
+void decode_CPR_local (aircraft *a, const modeS_message *mm, bool surface)
+{
+  pos_t  pos;
+  double max_range = surface ? MODES_LOCAL_RANGE_SURFACE : MODES_LOCAL_RANGE_AIR;
+
+  if (!Modes.home_pos_ok)
+     return;
+
+  if (!decode_CPR_relative(Modes.home_pos, mm->raw_latitude, mm->raw_longitude,
+                           mm->odd_flag != 0, surface, &pos))
+  {
+    Modes.stat.CPR_range_errors++;
+    pos.lat = pos.lon = 0.0;
+    position_rejected (a, pos, "local-latitude", 0.0, 0.0);
+    return;
+  }
+
+  if (great_circle_dist(pos, Modes.home_pos) > max_range)
+  {
+    Modes.stat.CPR_range_errors++;
+    position_rejected (a, pos, surface ? "local-range-surface" : "local-range",
+                       great_circle_dist(pos, Modes.home_pos), max_range);
+    return;
   }
 
-  if (a->position.lon > 180)
-     a->position.lon -= 360;
+  if (!position_plausible(a, pos))
+     return;
 
+  Modes.stat.CPR_local_ok++;
+  a->position = pos;
   set_home_distance (a);
 }
 
@@ -3203,6 +4386,8 @@ aircraft *interactive_receive_data (const modeS_message *mm, uint64_t now)
   a->seen_last = now;
   a->messages++;
 
+  a->msg_time = modeS_msg_time (mm, now);
+
   /* Ensure number of elements is 2^n.
    */
   assert ((DIM(a->sig_levels) & -(int)DIM(a->sig_levels)) == DIM(a->sig_levels));
@@ -3210,6 +4395,29 @@ aircraft *interactive_receive_data (const modeS_message *mm, uint64_t now)
   a->sig_levels [a->sig_idx++] = mm->sig_level;
   a->sig_idx &= DIM(a->sig_levels) - 1;
 
+  /* Messages from the network have no signal-level.
+   */
+  if (mm->sig_level > 0)
+  {
+    double dB = 10 * log10 (mm->sig_level);
+    double t  = (double) (now - a->seen_first) / 1000.0;
+
+    if (a->sig_num == 0 || mm->sig_level < a->sig_min)
+       a->sig_min = mm->sig_level;
+    if (mm->sig_level > a->sig_max)
+       a->sig_max = mm->sig_level;
+    a->sig_sum += mm->sig_level;
+    a->sig_num++;
+
+    a->sig_t_sum    += t;
+    a->sig_tt_sum   += t * t;
+    a->sig_dB_sum   += dB;
+    a->sig_t_dB_sum += t * dB;
+
+    if (Modes.home_pos_ok && a->distance > 0 && VALID_POS(a->position))
+       set_RSSI_sector (a, dB);
+  }
+
   if (mm->msg_type == 5 || mm->msg_type == 21)
   {
     if (mm->identity)
@@ -3235,6 +4443,7 @@ aircraft *interactive_receive_data (const modeS_message *mm, uint64_t now)
              (mm->ME_type >= 20 && mm->ME_type <= 22))   /* Airborne Position (GNSS Height) */
     {
       a->altitude = mm->altitude;
+      a->NIC      = get_NIC (mm->ME_type, a->NIC_supp_A);
       if (mm->odd_flag)
       {
         a->odd_CPR_lat  = mm->raw_latitude;
@@ -3261,8 +4470,33 @@ aircraft *interactive_receive_data (const modeS_message *mm, uint64_t now)
 
       if (llabs(t_diff) <= 60*10*1000)
            decode_CPR (a);
+      else if (!VALID_POS(a->position))
+           decode_CPR_local (a, mm, false);
    // else LOG_FILEONLY ("t_diff for '%04X' too large: %lld sec.\n", a->addr, t_diff/1000);
     }
+    else if (mm->ME_type >= 5 && mm->ME_type <= 8)   /* Surface Position */
+    {
+      a->NIC = get_NIC (mm->ME_type, a->NIC_supp_A);
+      decode_CPR_local (a, mm, true);
+    }
+    else if (mm->ME_type == 31 && (mm->ME_subtype == 0 || mm->ME_subtype == 1))
+    {
+      if (!a->op_status_ok)   /* count each aircraft once */
+      {
+        if (mm->ADSB_version < DIM(Modes.stat.ADSB_version))
+           Modes.stat.ADSB_version [mm->ADSB_version]++;
+        if ((mm->ADSB_version == 1 || mm->ADSB_version == 2) && mm->NACp < 8)
+           Modes.stat.NACp_low++;
+      }
+      a->op_status_ok = true;
+      a->ADSB_version = mm->ADSB_version;
+      if (mm->ADSB_version == 1 || mm->ADSB_version == 2)
+      {
+        a->NACp       = mm->NACp;
+        a->SIL        = mm->SIL;
+        a->NIC_supp_A = mm->NIC_supp_A;
+      }
+    }
     else if (mm->ME_type == 19)
     {
       if (mm->ME_subtype == 1 || mm->ME_subtype == 2)
@@ -3273,6 +4507,43 @@ aircraft *interactive_receive_data (const modeS_message *mm, uint64_t now)
       }
     }
   }
+
+  /* The Comm-B register guessed by `decode_comm_B()`.
+   */
+  if (mm->msg_type == 20 || mm->msg_type == 21)
+  {
+    switch (mm->BDS)
+    {
+      case 0x20:
+           memcpy (a->flight, mm->flight, sizeof(a->flight));
+           p = a->flight + sizeof(a->flight)-1;
+           while (p >= a->flight && (*p == ' ' || *p == '\0'))
+             *p-- = '\0';  /* Remove trailing spaces */
+           break;
+
+      case 0x40:
+           a->BDS_40_ok    = true;
+           a->sel_altitude = mm->sel_altitude;
+           a->baro_setting = mm->baro_setting;
+           break;
+
+      case 0x50:
+           a->BDS_50_ok     = true;
+           a->roll          = mm->roll;
+           a->true_track    = mm->true_track;
+           a->ground_speed  = mm->ground_speed;
+           a->true_airspeed = mm->true_airspeed;
+           break;
+
+      case 0x60:
+           a->BDS_60_ok      = true;
+           a->mag_heading    = mm->mag_heading;
+           a->IAS            = mm->IAS;
+           a->mach           = mm->mach;
+           a->baro_vert_rate = mm->baro_vert_rate;
+           break;
+    }
+  }
   return (a);
 }
 
@@ -3281,6 +4552,7 @@ aircraft *interactive_receive_data (const modeS_message *mm, uint64_t now)
  *
  * If `a->show == A_SHOW_FIRST_TIME`, print in GREEN colour.
  * If `a->show == A_SHOW_LAST_TIME`, print in RED colour.
+ * A suspect aircraft has a `!` after the ICAO address.
  *
  * \param in a    the aircraft to show.
  * \param in now  the currect tick-timer in milli-seconds.
@@ -3380,7 +4652,7 @@ void interactive_show_aircraft (const aircraft *a, uint64_t now)
   if (ms_diff < 0LL)  /* clock wrapped */
      ms_diff = 0L;
 
-  printf ("%06X %-9.9s %-8s %-5s     %-5s %-7s %-8s   %-5s ", a->addr, flight, reg_num, alt_buf, speed_buf, lat_buf, lon_buf, heading_buf);
+  printf ("%06X%c%-9.9s %-8s %-5s     %-5s %-7s %-8s   %-5s ", a->addr, a->suspect ? '!' : ' ', flight, reg_num, alt_buf, speed_buf, lat_buf, lon_buf, heading_buf);
   printf ("%6s  %5s %5u  %2llu sec \n", distance_buf, RSSI_buf, a->messages, ms_diff / 1000);
 
   if (restore_colour)
@@ -3436,6 +4708,114 @@ void interactive_show_data (uint64_t now)
     count++;
   }
   old_count = count;
+
+  if (Modes.net && !Modes.net_active)
+     interactive_show_net_rates();
+}
+
+/**
+ * Show the send rate of each network output in the last `MODES_NET_RATE_INTERVAL`,
+ * the rate of JSON requests and an estimate of the traffic per day.
+ */
+void interactive_show_net_rates (void)
+{
+  static const intptr_t services[] = { MODES_NET_SERVICE_RAW_OUT,
+                                       MODES_NET_SERVICE_SBS_OUT,
+                                       MODES_NET_SERVICE_HTTP
+                                     };
+  static const char *names[] = { "Raw-out", "SBS-out", "HTTP" };
+  double   sum = 0.0;
+  unsigned i;
+
+  printf ("Sent kB/s:");
+  for (i = 0; i < DIM(services); i++)
+  {
+    printf (" %s %6.2f,", names[i], Modes.net_rate[services[i]] / 1024.0);
+    sum += Modes.net_rate [services[i]];
+  }
+  printf (" %5.2f JSON polls/s, approx. %7.1f MB/day.\n",
+          Modes.JSON_poll_rate, sum * 86400.0 / (1024.0 * 1024.0));
+}
+
+/**
+ * Every `MODES_NET_RATE_INTERVAL`, update the send rate of each network service
+ * and the rate of JSON requests from the traffic since the last update.
+ */
+void net_rates_update (uint64_t now)
+{
+  double sec = (double) (now - Modes.net_rate_time) / 1000.0;
+  int    s;
+
+  if (now - Modes.net_rate_time < MODES_NET_RATE_INTERVAL)
+     return;
+
+  if (Modes.net_rate_time > 0)
+  {
+    for (s = MODES_NET_SERVICE_RAW_OUT; s < MODES_NET_SERVICES_NUM; s++)
+        Modes.net_rate [s] = (double) (Modes.stat.bytes_sent[s] - Modes.net_rate_bytes[s]) / sec;
+    Modes.JSON_poll_rate = (double) (Modes.stat.HTTP_JSON_requests - Modes.net_rate_JSON) / sec;
+  }
+
+  for (s = MODES_NET_SERVICE_RAW_OUT; s < MODES_NET_SERVICES_NUM; s++)
+      Modes.net_rate_bytes [s] = Modes.stat.bytes_sent [s];
+  Modes.net_rate_JSON = Modes.stat.HTTP_JSON_requests;
+  Modes.net_rate_time = now;
+}
+
+/**
+ * Add the RSSI `dB` of a message from this aircraft to the bearing sector of its
+ * last position. Normalised to 1 Nm from home.
+ */
+void set_RSSI_sector (const aircraft *a, double dB)
+{
+  double       bearing = great_circle_bearing (Modes.home_pos, a->position);
+  int          sector  = (int) (bearing * MODES_RSSI_SECTORS / 360.0);
+  RSSI_sector *rs      = &Modes.stat.RSSI_sectors [sector % MODES_RSSI_SECTORS];
+
+  rs->dB_sum   += dB + 20 * log10 (a->distance / 1852.0);
+  rs->dist_sum += a->distance;
+  rs->num++;
+}
+
+/**
+ * Return the RSSI trend of an aircraft in dB per minute.
+ * This is the slope of a least-squares line through all its signal-levels.
+ * A positive trend is an approaching aircraft or a rising signal.
+ *
+ * \retval 0.0 if there are too few messages.
+ */
+double aircraft_RSSI_trend (const aircraft *a)
+{
+  double n = (double) a->sig_num;
+  double d = n * a->sig_tt_sum - a->sig_t_sum * a->sig_t_sum;
+
+  if (a->sig_num < 2 || d < SMALL_VAL)
+     return (0.0);
+  return (60.0 * (n * a->sig_t_dB_sum - a->sig_t_sum * a->sig_dB_sum) / d);
+}
+
+/**
+ * Log the signal-levels of an aircraft that is about to be removed.
+ * A low maximum RSSI for aircrafts close to home in some direction
+ * could point to an antenna problem. Hence the distance and bearing
+ * from home of the last position is logged too.
+ * See `show_RSSI_statistics()` for a summary per bearing sector.
+ */
+void log_aircraft_RSSI (const aircraft *a)
+{
+  char distance [50] = "";
+
+  if (!Modes.log || a->sig_num == 0)
+     return;
+
+  if (a->distance > 0 && VALID_POS(a->position))
+     snprintf (distance, sizeof(distance), ", %.1f %s at %.0f deg",
+               a->distance / (Modes.metric ? 1000.0 : 1852.0), Modes.metric ? "km" : "Nm",
+               great_circle_bearing(Modes.home_pos, a->position));
+
+  LOG_FILEONLY ("%06X: RSSI min/avg/max: %.1lf/%.1lf/%.1lf dBFS, trend %+.2lf dB/min, %u messages%s.\n",
+                a->addr, 10 * log10(a->sig_min), 10 * log10(a->sig_sum / a->sig_num), 10 * log10(a->sig_max),
+                aircraft_RSSI_trend(a), a->sig_num, distance);
 }
 
 /**
@@ -3462,6 +4842,8 @@ void remove_stale_aircrafts (uint64_t now)
     }
     else if (diff > Modes.interactive_ttl)
     {
+      log_aircraft_RSSI (a);
+
       /* Remove the element from the linked list.
        */
       LIST_DELETE (aircraft, &Modes.aircrafts, a);
@@ -3482,6 +4864,7 @@ void free_all_aircrafts (void)
   {
     aircraft *next = a->next;
 
+    log_aircraft_RSSI (a);
     free (a);
     if (!prev)
          Modes.aircrafts = next;
@@ -3545,16 +4928,64 @@ char *receiver_to_json (void)
   if (!Modes.json_aircraft_history [history_size].ptr)
      history_size = Modes.json_aircraft_history_next;
 
-  return mg_mprintf ("{%Q: %s, "    // "version", DUMP1090_VERSION
-                      "%Q: %llu, "  // "refresh", Modes.json_interval
-                      "%Q: %d, "    // "history", history_size
-                      "%Q: %.6g, "  // "lat",     Modes.home_pos.lat; if 'Modes.home_pos_ok == false', this is 0.
-                      "%Q: %.6g}",  // "lon",     Modes.home_pos.lon; ditto
-                      "version", DUMP1090_VERSION,
-                      "refresh", Modes.json_interval,
-                      "history", history_size,
-                      "lat",     Modes.home_pos.lat,
-                      "lon",     Modes.home_pos.lon);
+  return mg_mprintf ("{%Q: %s, "    // "version",     DUMP1090_VERSION
+                      "%Q: %llu, "  // "refresh",     Modes.json_interval
+                      "%Q: %d, "    // "history",     history_size
+                      "%Q: %.6g, "  // "lat",         Modes.home_pos.lat; if 'Modes.home_pos_ok == false', this is 0.
+                      "%Q: %.6g, "  // "lon",         Modes.home_pos.lon; ditto
+                      "%Q: %s}",    // "outlineJson", Modes.home_pos_ok
+                      "version",     DUMP1090_VERSION,
+                      "refresh",     Modes.json_interval,
+                      "history",     history_size,
+                      "lat",         Modes.home_pos.lat,
+                      "lon",         Modes.home_pos.lon,
+                      "outlineJson", Modes.home_pos_ok ? "true" : "false");
+}
+
+/**
+ * Return a malloced JSON description of the actual range outline.
+ * For each bearing sector, the furthest position seen:
+ * ```
+ *  { "points": [[60.2806, 5.2237, 35000], ...] }
+ * ```
+ *
+ * A Tar1090 web-client reads this from `/data/outline.json`
+ * when `receiver.json` has `"outlineJson": true`.
+ */
+char *outline_to_json (void)
+{
+  char *buf = malloc (20 + 50 * MODES_RANGE_BEARINGS);
+  char *p = buf;
+  int   i, num = 0;
+
+  if (!buf)
+     return (NULL);
+
+  p += sprintf (p, "{\"points\": [");
+  for (i = 0; i < MODES_RANGE_BEARINGS; i++)
+  {
+    const range_point *rp = &Modes.range_outline [i];
+
+    if (rp->distance == 0.0)
+       continue;
+    p += sprintf (p, "%s[%.4f, %.4f, %d]", num++ ? ", " : "", rp->pos.lat, rp->pos.lon, rp->altitude);
+  }
+  strcpy (p, "]}");
+  return (buf);
+}
+
+/**
+ * Move `*p` past the `l` characters just written by `snprintf()` and reduce `*buflen`.
+ * A truncated result is clamped; `*p` never moves past the end of the buffer.
+ */
+static void json_advance (char **p, int *buflen, int l)
+{
+  if (l < 0)
+     l = 0;
+  else if (l >= *buflen)
+     l = *buflen - 1;   /* truncated */
+  *p      += l;
+  *buflen -= l;
 }
 
 /**
@@ -3652,8 +5083,7 @@ char *aircrafts_to_json (int *num_planes, bool extended_client)
     l = snprintf (p, buflen, "{\"now\": %lu.%03lu, \"messages\": %llu, \"aircraft\" : [",
                   tv_now.tv_sec, tv_now.tv_usec/1000, Modes.stat.messages_total);
 
-    p      += l;
-    buflen -= l;
+    json_advance (&p, &buflen, l);
   }
   else
   {
@@ -3686,26 +5116,76 @@ char *aircrafts_to_json (int *num_planes, bool extended_client)
       l = snprintf (p, buflen,
                     "{\"hex\": \"%06X\", \"flight\": \"%.*s\", \"lat\": %f, \"lon\": %f, \"altitude\": %d, \"track\": %d, \"speed\": %d",
                     a->addr, f_len, a->flight, a->position.lat, a->position.lon, altitude, a->heading, speed);
-      p      += l;
-      buflen -= l;
+      json_advance (&p, &buflen, l);
+
+      if (a->suspect)
+      {
+        l = snprintf (p, buflen, ", \"suspect\": \"%s\"", aircraft_suspect_name(a->suspect));
+        json_advance (&p, &buflen, l);
+      }
 
       if (extended_client)
       {
         l = snprintf (p, buflen, ", \"type\": \"%s\", \"messages\": %u, \"seen\": %lu, \"seen_pos\": %lu",
                       "adsb_icao", a->messages, 2, 1 /* tv_now.tv_sec - a->seen_first/1000 */);
-        p      += l;
-        buflen -= l;
+        json_advance (&p, &buflen, l);
+
+        if (a->op_status_ok)
+        {
+          if (a->ADSB_version == 1 || a->ADSB_version == 2)
+               l = snprintf (p, buflen, ", \"version\": %d, \"nac_p\": %d, \"sil\": %d",
+                             a->ADSB_version, a->NACp, a->SIL);
+          else l = snprintf (p, buflen, ", \"version\": %d", a->ADSB_version);
+          json_advance (&p, &buflen, l);
+        }
+        if (a->NIC >= 0)
+        {
+          l = snprintf (p, buflen, ", \"nic\": %d", a->NIC);
+          json_advance (&p, &buflen, l);
+        }
+        if (a->BDS_40_ok)
+        {
+          l = snprintf (p, buflen, ", \"nav_altitude_mcp\": %d", a->sel_altitude);
+          json_advance (&p, &buflen, l);
+          if (a->baro_setting > 0)
+          {
+            l = snprintf (p, buflen, ", \"nav_qnh\": %.1lf", a->baro_setting);
+            json_advance (&p, &buflen, l);
+          }
+        }
+        if (a->BDS_50_ok)
+        {
+          l = snprintf (p, buflen, ", \"roll\": %.1lf, \"true_track\": %.1lf, \"gs\": %d",
+                        a->roll, a->true_track, a->ground_speed);
+          json_advance (&p, &buflen, l);
+          if (a->true_airspeed > 0)
+          {
+            l = snprintf (p, buflen, ", \"tas\": %d", a->true_airspeed);
+            json_advance (&p, &buflen, l);
+          }
+        }
+        if (a->BDS_60_ok)
+        {
+          l = snprintf (p, buflen, ", \"mag_heading\": %.1lf, \"ias\": %d, \"mach\": %.3lf, \"baro_rate\": %d",
+                        a->mag_heading, a->IAS, a->mach, a->baro_vert_rate);
+          json_advance (&p, &buflen, l);
+        }
+        if (a->sig_num > 0)
+        {
+          l = snprintf (p, buflen, ", \"rssi\": %.1lf, \"rssi_trend\": %.2lf",
+                        10 * log10(a->sig_sum / a->sig_num), aircraft_RSSI_trend(a));
+          json_advance (&p, &buflen, l);
+        }
       }
 
-      strcpy (p, "},\n");
-      p      += 3;
-      buflen -= 3;
+      l = snprintf (p, buflen, "},\n");
+      json_advance (&p, &buflen, l);
 
       (*num_planes)++;
 
       /* Resize if needed.
        */
-      if (buflen < 256)
+      if (buflen < 512)
       {
         int used = p - buf;
 
@@ -3917,6 +5397,30 @@ u_short handler_port (intptr_t service)
   return (modeS_net_services [service].port);
 }
 
+/**
+ * Set the listening port of a service from a `--net-xx-port` argument.
+ * The argument is either `port` or `addr:port` (or `[addr6]:port`).
+ * In the latter case the service will listen on `addr` only.
+ */
+void handler_set_port (intptr_t service, const char *arg)
+{
+  net_service *serv = &modeS_net_services [service];
+  net_service  bind;
+
+  if (arg[0] == '[' || strchr(arg, ':'))
+  {
+    /* Do not touch `serv->host`; that is the remote host for `--net-active`.
+     */
+    memset (&bind, '\0', sizeof(bind));
+    set_host_port (arg, &bind, serv->port);
+    serv->bind_addr = bind.host;
+    serv->bind_ip6  = bind.is_ip6;
+    serv->port      = bind.port;
+  }
+  else
+    serv->port = atoi (arg);
+}
+
 char *handler_error (intptr_t service)
 {
   assert (service >= MODES_NET_SERVICE_RAW_OUT && service < MODES_NET_SERVICES_NUM);
@@ -4014,49 +5518,194 @@ const char *get_client_headers (const connection *cli, const char *hdr)
 #include "favicon.c"
 
 /**
- * The event handler for HTTP traffic.
+ * Compare the credentials `given` by a HTTP client with the `secret` in a
+ * time that does not depend on where they differ.
  */
-int connection_handler_http (mg_connection *conn,
-                             int            ev,
-                             void          *ev_data,
-                             char          *request_data,
-                             size_t         request_size,
-                             char         **ret_data)
+bool http_auth_equal (const char *given, const char *secret)
 {
-  mg_http_message *hm = ev_data;
-  connection      *cli;
-  bool             is_dump1090, is_extended;
-  const char      *content = NULL;
-  const char      *uri, *ext;
-  char            *request, *end;
-  char             header [1000];
-  int              header_len;
+  size_t  given_len  = strlen (given);
+  size_t  secret_len = strlen (secret);
+  size_t  i;
+  uint8_t diff = (given_len != secret_len);
+
+  for (i = 0; i < secret_len; i++)
+      diff |= (uint8_t) secret[i] ^ (uint8_t) (i < given_len ? given[i] : 0);
+  return (diff == 0);
+}
 
-  *ret_data = NULL;
-  *request_data = '\0';
+/**
+ * Check the argument of the `--web-auth` option.
+ * A token is sent back in a `Set-Cookie` header. So it must not be longer
+ * than `MODES_WEB_AUTH_MAX` and all characters must be legal in a cookie value.
+ *
+ * \retval false if `auth` is not legal.
+ */
+bool web_auth_check (const char *auth)
+{
+  const char *p;
 
-  if ((ev != MG_EV_HTTP_MSG && ev != MG_EV_HTTP_CHUNK) || strncmp(hm->head.ptr, "GET /", 5))
-     return (400);  /* Bad Request */
+  if (*auth == '\0' || *auth == ':' || strlen(auth) > MODES_WEB_AUTH_MAX)
+     return (false);
 
-  request = strncpy (alloca(hm->head.len+1), hm->head.ptr, hm->head.len);
-  strncpy (request_data, request, request_size);
+  if (strchr(auth, ':'))   /* a `user:password` is not put in a cookie */
+     return (true);
 
-  uri = request + strlen ("GET ");
-  end = strchr (uri, ' ');
-  if (!end)
+  for (p = auth; *p; p++)
   {
-    conn->is_closing = 1;
-    return (400);         /* Bad Request */
+    /* RFC 6265: no CTLs, whitespace, DQUOTE, comma, semicolon or backslash.
+     */
+    if (*p <= 0x20 || *p >= 0x7F || *p == '"' || *p == ',' || *p == ';' || *p == '\\')
+       return (false);
   }
+  return (true);
+}
 
-  *end = '\0';
+/**
+ * Generate a random token for `--web-auth random`.
+ * `rand_s()` gets its numbers from the cryptographic random generator of Windows.
+ *
+ * \retval NULL if `rand_s()` failed.
+ */
+const char *web_auth_random (void)
+{
+  static const char chars[] = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+  static char       token [33];
+  unsigned int      r;
+  int               i;
+
+  for (i = 0; i < (int)sizeof(token) - 1; i++)
+  {
+    if (rand_s(&r) != 0)
+       return (NULL);
+    token [i] = chars [r % (sizeof(chars) - 1)];
+  }
+  token [i] = '\0';
+  return (token);
+}
+
+/**
+ * Check the credentials of a HTTP request against the `--web-auth` option.
+ * This is either:
+ *  \li a `user:password` for Basic authentication.
+ *  \li or a token given in a `Authorization: Bearer <token>` header,
+ *      an `access_token=<token>` cookie or a `?access_token=<token>` query.
+ *
+ * A browser can only pass the token in the query of the first page.
+ * Hence `*from_query` is set and the caller should redirect and set the
+ * `access_token` cookie for the XHR requests of the Web-page.
+ *
+ * \retval true if no `--web-auth` was given or the credentials matches.
+ */
+bool http_auth_ok (mg_http_message *hm, bool *from_query)
+{
+  char user [100], pass [100], given [sizeof(user) + sizeof(pass)];
+
+  *from_query = false;
+  if (!Modes.web_auth)
+     return (true);
+
+  if (strchr(Modes.web_auth, ':'))
+  {
+    mg_http_creds (hm, user, sizeof(user), pass, sizeof(pass));
+    if (!user[0])
+       return (false);
+    snprintf (given, sizeof(given), "%s:%s", user, pass);
+    return (http_auth_equal(given, Modes.web_auth));
+  }
+
+  /* Check the query first. `mg_http_creds()` would otherwise return the
+   * `?access_token=` of a first visit as if it came from a header.
+   */
+  if (mg_http_get_var(&hm->query, "access_token", pass, sizeof(pass)) > 0 &&
+      http_auth_equal(pass, Modes.web_auth))
+  {
+    *from_query = true;
+    return (true);
+  }
+
+  /* This gets the `Authorization` header or else the `access_token` cookie.
+   * Only trust it if one of those headers was really sent.
+   */
+  if (!mg_http_get_header(hm, "Authorization") && !mg_http_get_header(hm, "Cookie"))
+     return (false);
+
+  mg_http_creds (hm, user, sizeof(user), pass, sizeof(pass));
+  return (!user[0] && pass[0] && http_auth_equal(pass, Modes.web_auth));
+}
+
+/**
+ * The event handler for HTTP traffic.
+ */
+int connection_handler_http (mg_connection *conn,
+                             int            ev,
+                             void          *ev_data,
+                             char          *request_data,
+                             size_t         request_size,
+                             char         **ret_data)
+{
+  mg_http_message *hm = ev_data;
+  connection      *cli;
+  bool             is_dump1090, is_extended;
+  const char      *content = NULL;
+  const char      *uri, *ext;
+  char            *request, *end;
+  char             header [1000];
+  int              header_len;
+  bool             auth_query;
 
-  end = strchr (request_data+4, ' '); /* extract only the important file-part */
+  *ret_data = NULL;
+  *request_data = '\0';
+
+  if ((ev != MG_EV_HTTP_MSG && ev != MG_EV_HTTP_CHUNK) || strncmp(hm->head.ptr, "GET /", 5))
+     return (400);  /* Bad Request */
+
+  request = strncpy (alloca(hm->head.len+1), hm->head.ptr, hm->head.len);
+  strncpy (request_data, request, request_size);
+
+  uri = request + strlen ("GET ");
+  end = strchr (uri, ' ');
+  if (!end)
+  {
+    conn->is_closing = 1;
+    return (400);         /* Bad Request */
+  }
+
+  *end = '\0';
+
+  /* Extract only the important file-part. Drop the query too; it could
+   * hold a `?access_token=` that should not end up in the `--logfile`.
+   */
+  end = strpbrk (request_data+4, " ?");
   if (end)
      *end = '\0';
 
   Modes.stat.HTTP_get_requests++;
 
+  if (!http_auth_ok(hm, &auth_query))
+  {
+    const char *hdr = strchr (Modes.web_auth, ':') ? "WWW-Authenticate: Basic realm=\"dump1090\"\r\n"
+                                                   : "WWW-Authenticate: Bearer realm=\"dump1090\"\r\n";
+
+    mg_http_reply (conn, 401, hdr, "Unauthorized\n");
+    Modes.stat.HTTP_401_responses++;
+    return (401);
+  }
+
+  /* A good `?access_token=` query. Set the cookie and redirect to the page
+   * without the query. The browser then sends the cookie with every request.
+   */
+  if (auth_query)
+  {
+    char *redirect = mg_mprintf ("Set-Cookie: access_token=%s; Path=/; HttpOnly; SameSite=Strict\r\n"
+                                 "Location: %.*s\r\n", Modes.web_auth, (int)hm->uri.len, hm->uri.ptr);
+    if (!redirect)
+       return (444);  /* No Response */
+
+    mg_http_reply (conn, 303, redirect, "");
+    free (redirect);
+    return (303);
+  }
+
   if (str_startswith(request, "GET /data/receiver.json"))
   {
     char *data = receiver_to_json();
@@ -4071,6 +5720,18 @@ int connection_handler_http (mg_connection *conn,
     return (200);
   }
 
+  if (str_startswith(request, "GET /data/outline.json"))
+  {
+    char *data = outline_to_json();
+
+    if (!data)
+       return (444);  /* No Response */
+
+    *ret_data = data;
+    mg_http_reply (conn, 200, MODES_CONTENT_TYPE_JSON "\r\n", "%s", data);
+    return (200);
+  }
+
   if (str_startswith(request, "GET /chunks/chunks.json"))
   {
   }
@@ -4094,6 +5755,8 @@ int connection_handler_http (mg_connection *conn,
       return (444);       /* No Response */
     }
     *ret_data = data;
+    Modes.stat.HTTP_JSON_requests++;
+    Modes.stat.HTTP_JSON_bytes += strlen (data);
 
     /* This is rather inefficient way to pump data over to the client.
      * Better use a WebSocket instead.
@@ -4381,11 +6044,16 @@ void connection_handler (mg_connection *this_conn, int ev, void *ev_data, void *
 mg_connection *connection_setup (intptr_t service, bool listen, bool sending)
 {
   mg_connection *conn = NULL;
-  char           url [50];
+  char           url [100];
 
   if (listen)
   {
-    snprintf (url, sizeof(url), "tcp://0.0.0.0:%u", modeS_net_services[service].port);
+    /* Listen on all interfaces unless a `--net-xx-port addr:port` was given.
+     */
+    const char *host = modeS_net_services[service].bind_addr;
+    const char *fmt  = (modeS_net_services[service].bind_ip6 ? "tcp://[%s]:%u" : "tcp://%s:%u");
+
+    snprintf (url, sizeof(url), fmt, host ? host : "0.0.0.0", modeS_net_services[service].port);
     if (service == MODES_NET_SERVICE_HTTP)
          conn = mg_http_listen (&Modes.mgr, url, connection_handler, (void*) service);
     else conn = mg_listen (&Modes.mgr, url, connection_handler, (void*) service);
@@ -4413,16 +6081,237 @@ mg_connection *connection_setup (intptr_t service, bool listen, bool sending)
   return (conn);
 }
 
+/**
+ * Return the PID and program-name of the process listening on the TCP
+ * address and port in `sa` (an AF_INET or AF_INET6 address).
+ * A listener conflicts if it is on the same address or if either side
+ * is on the "any" address.
+ * Returns 0 if no such process was found.
+ */
+DWORD net_port_owner (const struct sockaddr *sa, char *prog_name, size_t prog_size)
+{
+  void  *table;
+  DWORD  i, pid = 0, size = 0;
+  ULONG  family = sa->sa_family;
+  HANDLE proc;
+
+  strncpy (prog_name, "?", prog_size);
+
+  if (GetExtendedTcpTable(NULL, &size, FALSE, family, TCP_TABLE_OWNER_PID_LISTENER, 0) != ERROR_INSUFFICIENT_BUFFER)
+     return (0);
+
+  table = malloc (size);
+  if (!table)
+     return (0);
+
+  if (GetExtendedTcpTable(table, &size, FALSE, family, TCP_TABLE_OWNER_PID_LISTENER, 0) == NO_ERROR)
+  {
+    if (family == AF_INET)
+    {
+      const MIB_TCPTABLE_OWNER_PID *tab4 = table;
+      const struct sockaddr_in     *sa4  = (const struct sockaddr_in*) sa;
+
+      for (i = 0; i < tab4->dwNumEntries; i++)
+      {
+        const MIB_TCPROW_OWNER_PID *row = tab4->table + i;
+
+        if ((u_short)row->dwLocalPort != sa4->sin_port)
+           continue;
+
+        if (row->dwLocalAddr == sa4->sin_addr.s_addr ||
+            row->dwLocalAddr == htonl(INADDR_ANY)    ||
+            sa4->sin_addr.s_addr == htonl(INADDR_ANY))
+        {
+          pid = row->dwOwningPid;
+          break;
+        }
+      }
+    }
+    else
+    {
+      const MIB_TCP6TABLE_OWNER_PID *tab6 = table;
+      const struct sockaddr_in6     *sa6  = (const struct sockaddr_in6*) sa;
+
+      for (i = 0; i < tab6->dwNumEntries; i++)
+      {
+        const MIB_TCP6ROW_OWNER_PID *row = tab6->table + i;
+        const IN6_ADDR              *local = (const IN6_ADDR*) row->ucLocalAddr;
+
+        if ((u_short)row->dwLocalPort != sa6->sin6_port)
+           continue;
+
+        if (!memcmp(local, &sa6->sin6_addr, sizeof(*local)) ||
+            IN6_IS_ADDR_UNSPECIFIED(local) ||
+            IN6_IS_ADDR_UNSPECIFIED(&sa6->sin6_addr))
+        {
+          pid = row->dwOwningPid;
+          break;
+        }
+      }
+    }
+  }
+  free (table);
+
+  if (pid == 0)
+     return (0);
+
+  proc = OpenProcess (PROCESS_QUERY_LIMITED_INFORMATION, FALSE, pid);
+  if (proc)
+  {
+    DWORD len = (DWORD) prog_size;
+
+    if (!QueryFullProcessImageNameA(proc, 0, prog_name, &len))
+       strncpy (prog_name, "?", prog_size);
+    CloseHandle (proc);
+  }
+  return (pid);
+}
+
+/**
+ * Called before the listening services are started.
+ *
+ * Do a test `bind()` on the address and port of each listening service.
+ * For each port already taken, report the service, the port and the
+ * program that holds it. Otherwise the user would only see a
+ * "Fail to set-up listen socket(s)" error.
+ *
+ * \retval the number of ports already in use or with an illegal bind address.
+ */
+int net_check_ports (void)
+{
+  static const intptr_t services[] = { MODES_NET_SERVICE_RAW_OUT,
+                                       MODES_NET_SERVICE_RAW_IN,
+                                       MODES_NET_SERVICE_SBS_OUT,
+                                       MODES_NET_SERVICE_HTTP
+                                     };
+  unsigned i;
+  int      num = 0;
+
+  for (i = 0; i < DIM(services); i++)
+  {
+    struct sockaddr_in  sa4;
+    struct sockaddr_in6 sa6;
+    struct sockaddr    *sa;
+    int         sa_len, rc;
+    SOCKET      s;
+    uint16_t    port = handler_port (services[i]);
+    const char *host = modeS_net_services [services[i]].bind_addr;
+    char        prog_name [MG_PATH_MAX];
+    DWORD       pid;
+    int         on = 1;
+
+    if (modeS_net_services[services[i]].bind_ip6)
+    {
+      memset (&sa6, '\0', sizeof(sa6));
+      sa6.sin6_family = AF_INET6;
+      sa6.sin6_port   = htons (port);
+      rc     = inet_pton (AF_INET6, host, &sa6.sin6_addr);
+      sa     = (struct sockaddr*) &sa6;
+      sa_len = sizeof(sa6);
+    }
+    else
+    {
+      memset (&sa4, '\0', sizeof(sa4));
+      sa4.sin_family      = AF_INET;
+      sa4.sin_addr.s_addr = htonl (INADDR_ANY);
+      sa4.sin_port        = htons (port);
+      rc     = host ? inet_pton (AF_INET, host, &sa4.sin_addr) : 1;
+      sa     = (struct sockaddr*) &sa4;
+      sa_len = sizeof(sa4);
+    }
+
+    if (rc != 1)
+    {
+      LOG_STDERR ("Illegal bind address '%s' for service \"%s\".\n", host, handler_descr(services[i]));
+      num++;
+      continue;
+    }
+
+    /* Report any process already listening on this address and port.
+     */
+    pid = net_port_owner (sa, prog_name, sizeof(prog_name));
+    if (pid)
+    {
+      LOG_STDERR ("Port %u for service \"%s\" is already in use by PID %lu (%s).\n",
+                  port, handler_descr(services[i]), pid, prog_name);
+      num++;
+      continue;
+    }
+
+    s = socket (sa->sa_family, SOCK_STREAM, IPPROTO_TCP);
+    if (s == INVALID_SOCKET)
+       continue;
+
+    /* On Windows, `SO_REUSEADDR` would let this `bind()` succeed on a port
+     * another process is listening on. Ask for exclusive use instead.
+     */
+    setsockopt (s, SOL_SOCKET, SO_EXCLUSIVEADDRUSE, (const char*)&on, sizeof(on));
+
+    if (bind(s, sa, sa_len) == SOCKET_ERROR)
+    {
+      int err = WSAGetLastError();
+
+      if (err == WSAEADDRINUSE)
+      {
+        LOG_STDERR ("Port %u for service \"%s\" is already in use.\n",
+                    port, handler_descr(services[i]));
+      }
+      else if (err == WSAEACCES)
+      {
+        /* Windows (or Hyper-V, WSL2 and Docker) reserves ranges of ports.
+         */
+        LOG_STDERR ("Port %u for service \"%s\" is not allowed. It could be in an excluded port range;\n"
+                    "see 'netsh interface ipv4 show excludedportrange protocol=tcp'.\n",
+                    port, handler_descr(services[i]));
+      }
+      else if (err == WSAEADDRNOTAVAIL)
+      {
+        LOG_STDERR ("Bind address '%s' for service \"%s\" is not an address of this host.\n",
+                    host ? host : "0.0.0.0", handler_descr(services[i]));
+      }
+      else
+      {
+        LOG_STDERR ("Cannot bind port %u for service \"%s\": %s.\n",
+                    port, handler_descr(services[i]), trace_strerror(err));
+      }
+      num++;
+    }
+    closesocket (s);
+  }
+  return (num);
+}
+
+/**
+ * Return true if a listening service is bound to a loopback address.
+ * The `bind_addr` of an IPv6 address is from `mg_ntoa()`; e.g. `::1` is
+ * `0:0:0:0:0:0:0:1`. Hence parse it instead of comparing the string.
+ */
+bool net_is_loopback (intptr_t service)
+{
+  const net_service *serv = &modeS_net_services [service];
+  struct in_addr     a4;
+  struct in6_addr    a6;
+
+  if (!serv->bind_addr)
+     return (false);
+
+  if (serv->bind_ip6)
+     return (inet_pton(AF_INET6, serv->bind_addr, &a6) == 1 && IN6_IS_ADDR_LOOPBACK(&a6));
+
+  return (inet_pton(AF_INET, serv->bind_addr, &a4) == 1 && (ntohl(a4.s_addr) >> 24) == 127);
+}
+
 /**
  * Initialize the Mongoose network manager and:
  *  \li start the 2 active network services.
- *  \li or start the 4 listening (passive) network services.
+ *  \li or check the ports and start the 4 listening (passive) network services.
  */
 int modeS_init_net (void)
 {
   struct stat st;
 
   mg_mgr_init (&Modes.mgr);
+  Modes.net_start = MSEC_TIME();
 
   if (Modes.net_active)
   {
@@ -4440,6 +6329,9 @@ int modeS_init_net (void)
   }
   else
   {
+    if (net_check_ports() > 0)
+       return (1);
+
     Modes.raw_out  = connection_setup (MODES_NET_SERVICE_RAW_OUT, true, true);
     Modes.raw_in   = connection_setup (MODES_NET_SERVICE_RAW_IN, true, false);
     Modes.sbs_out  = connection_setup (MODES_NET_SERVICE_SBS_OUT, true, true);
@@ -4450,6 +6342,10 @@ int modeS_init_net (void)
       LOG_STDERR ("Fail to set-up listen socket(s).\n");
       return (1);
     }
+
+    if (net_is_loopback(MODES_NET_SERVICE_HTTP))
+       LOG_STDOUT ("The HTTP server is bound to %s; the Web-page is reachable from this host only.\n",
+                   modeS_net_services [MODES_NET_SERVICE_HTTP].bind_addr);
   }
 
   if (Modes.http_out)
@@ -4475,16 +6371,35 @@ int modeS_init_net (void)
 
 /**
  * Write raw output to TCP clients.
+ *
+ * With `--net-ro-mlat` and a known timestamp, one of the AVR variants
+ * is written instead of `*<msg>;`:
+ *  \li `<<12 digit timestamp><2 digit signal-level><msg>;` for a message with a signal-level.
+ *  \li `@<12 digit timestamp><msg>;` for a message without (from the network).
  */
 void modeS_send_raw_output (const modeS_message *mm)
 {
-  char  msg [10 + 2*MODES_LONG_MSG_BYTES];
+  char  msg [30 + 2*MODES_LONG_MSG_BYTES];
   char *p = msg;
 
   if (!handler_sending(MODES_NET_SERVICE_RAW_OUT))
      return;
 
-  *p++ = '*';
+  if (Modes.net_ro_mlat && mm->timestamp_msg)
+  {
+    uint64_t timestamp = mm->timestamp_msg & 0xFFFFFFFFFFFFULL;   /* 48 bits */
+    int      sig_level = (int) (255.0 * sqrt(mm->sig_level) + 0.5);
+
+    if (sig_level > 255)
+       sig_level = 255;
+
+    if (mm->sig_level > 0)
+         p += sprintf (p, "<%012llX%02X", timestamp, sig_level);
+    else p += sprintf (p, "@%012llX", timestamp);
+  }
+  else
+    *p++ = '*';
+
   mg_hex (&mm->msg, mm->msg_bits/8, p);
   p = strchr (p, '\0');
   *p++ = ';';
@@ -4555,140 +6470,705 @@ void modeS_send_SBS_output (const modeS_message *mm, const aircraft *a)
   {
     int vr = (mm->vert_rate_sign == 0 ? 1 : -1) * 64 * (mm->vert_rate - 1);
 
-    p += sprintf (p, "MSG,4,,,%02X%02X%02X,,,,,,,,%d,%d,,,%i,,0,0,0,0",
-                  mm->AA1, mm->AA2, mm->AA3, a->speed, a->heading, vr);
-  }
-  else if (mm->msg_type == 21)
-  {
-    p += sprintf (p, "MSG,6,,,%02X%02X%02X,,,,,,,,,,,,,%d,%d,%d,%d,%d",
-                  mm->AA1, mm->AA2, mm->AA3, mm->identity, alert, emergency, spi, ground);
-  }
-  else
-    return;
+    p += sprintf (p, "MSG,4,,,%02X%02X%02X,,,,,,,,%d,%d,,,%i,,0,0,0,0",
+                  mm->AA1, mm->AA2, mm->AA3, a->speed, a->heading, vr);
+  }
+  else if (mm->msg_type == 21)
+  {
+    p += sprintf (p, "MSG,6,,,%02X%02X%02X,,,,,,,,,,,,,%d,%d,%d,%d,%d",
+                  mm->AA1, mm->AA2, mm->AA3, mm->identity, alert, emergency, spi, ground);
+  }
+  else
+    return;
+
+  *p++ = '\n';
+  connection_send (MODES_NET_SERVICE_SBS_OUT, msg, p - msg);
+}
+
+/**
+ * Turn an hex digit into its 4 bit decimal value.
+ * Returns -1 if the digit is not in the 0-F range.
+ */
+int hex_digit_val (int c)
+{
+  c = tolower (c);
+  if (c >= '0' && c <= '9')
+     return (c - '0');
+  if (c >= 'a' && c <= 'f')
+     return (c - 'a' + 10);
+  return (-1);
+}
+
+/**
+ * This function decodes a string representing a Mode S message in
+ * raw hex format like: `*8D4B969699155600E87406F5B69F;<eol>`
+ *
+ * Or one of the AVR variants with a 12 digit (48 bit) MLAT timestamp first:
+ *  \li `@0123456789AB8D4B969699155600E87406F5B69F;<eol>`
+ *  \li `<0123456789ABC88D4B969699155600E87406F5B69F;<eol>` with a 2 digit signal-level after the timestamp.
+ *
+ * The timestamp is kept in `mm.timestamp_msg` for the raw output with `--net-ro-mlat`.
+ *
+ * The string is supposed to be at the start of the client buffer
+ * and NUL-terminated. It accepts both '\n' and '\r\n' terminated records.
+ *
+ * The message is passed to the higher level layers, so it feeds
+ * the selected screen output, the network output and so forth.
+ *
+ * If the message looks invalid, it is silently discarded.
+ */
+void decode_hex_message (mg_iobuf *msg, int loop_cnt)
+{
+  modeS_message mm;
+  uint8_t       bin_msg [MODES_LONG_MSG_BYTES];
+  int           len, j, skip, sig_level = -1;
+  uint64_t      timestamp = 0;
+  uint8_t      *hex;
+  uint8_t      *end = memchr (msg->buf, '\n', msg->len);
+
+  if (!end)
+  {
+    if (!Modes.interactive)
+       LOG_STDOUT ("RAW(%d): Bogus msg: '%.*s'...\n", loop_cnt, (int)msg->len, msg->buf);
+    Modes.stat.unrecognized_raw++;
+    mg_iobuf_del (msg, 0, msg->len);
+    return;
+  }
+
+  *end++ = '\0';
+  if (end[-2] == '\r')
+     end[-2] = '\0';
+
+  /* Remove spaces on the left and on the right.
+   */
+  hex = msg->buf;
+  len = end - msg->buf - 1;
+  while (len && isspace(hex[len-1]))
+  {
+    hex[len-1] = '\0';
+    len--;
+  }
+  while (isspace(*hex))
+  {
+    hex++;
+    len--;
+  }
+
+  /* Check it's format.
+   */
+  if (len < 2)
+  {
+    Modes.stat.empty_raw++;
+    mg_iobuf_del (msg, 0, end - msg->buf);
+    return;
+  }
+  if ((hex[0] != '*' && hex[0] != '@' && hex[0] != '<') || !memchr(msg->buf, ';', len))
+  {
+    Modes.stat.unrecognized_raw++;
+    mg_iobuf_del (msg, 0, end - msg->buf);
+    return;
+  }
+
+  /* Check the timestamp (and signal-level) of a `@` or `<` message.
+   */
+  skip = (hex[0] == '@') ? 12 : (hex[0] == '<') ? 14 : 0;
+  for (j = 1; j <= skip; j++)
+  {
+    if (j >= len || hex_digit_val(hex[j]) == -1)
+    {
+      Modes.stat.unrecognized_raw++;
+      mg_iobuf_del (msg, 0, end - msg->buf);
+      return;
+    }
+    if (j <= 12)
+       timestamp = (timestamp << 4) | hex_digit_val (hex[j]);
+  }
+  if (hex[0] == '<')
+     sig_level = (hex_digit_val (hex[13]) << 4) | hex_digit_val (hex[14]);
+
+  /* Turn the message into binary.
+   */
+  hex += 1 + skip;   /* Skip `*` (or `@`, `<` and timestamp) and `;` */
+  len -= 2 + skip;
+  if (len <= 0 || len > 2*MODES_LONG_MSG_BYTES)   /* Empty or too long message... broken. */
+  {
+    Modes.stat.unrecognized_raw++;
+    mg_iobuf_del (msg, 0, end - msg->buf);
+    return;
+  }
+
+  for (j = 0; j < len; j += 2)
+  {
+    int high = hex_digit_val (hex[j]);
+    int low  = hex_digit_val (hex[j+1]);
+
+    if (high == -1 || low == -1)
+    {
+      Modes.stat.unrecognized_raw++;
+      mg_iobuf_del (msg, 0, end - msg->buf);
+      return;
+    }
+    bin_msg[j/2] = (high << 4) | low;
+  }
+  mg_iobuf_del (msg, 0, end - msg->buf);
+  Modes.stat.good_raw++;
+  decode_modeS_message (&mm, bin_msg);
+  if (sig_level >= 0)
+     mm.sig_level = (sig_level / 255.0) * (sig_level / 255.0);
+  mm.timestamp_msg = timestamp;
+  modeS_user_message (&mm);
+}
+
+/**
+ * Decode the message given with `--decode <hex>` and show all its fields.
+ * The `*` and `;` of the raw format are optional. Spaces are ignored.
+ *
+ * \retval 0 on success.
+ * \retval 1 if `arg` is not a legal 56 or 112 bit message.
+ */
+int decode_hex_arg (const char *arg)
+{
+  modeS_message mm;
+  uint8_t       bin_msg [MODES_LONG_MSG_BYTES];
+  char          hex [2*MODES_LONG_MSG_BYTES + 1];
+  int           len = 0, j;
+
+  for ( ; *arg; arg++)
+  {
+    if (isspace((unsigned char)*arg) || (*arg == '*' && len == 0) || *arg == ';')
+       continue;
+    if (hex_digit_val((unsigned char)*arg) == -1 || len >= 2*MODES_LONG_MSG_BYTES)
+    {
+      LOG_STDERR ("Illegal Mode S message: '%s'.\n", Modes.decode_hex);
+      return (1);
+    }
+    hex [len++] = *arg;
+  }
+
+  if (len != 2*MODES_SHORT_MSG_BYTES && len != 2*MODES_LONG_MSG_BYTES)
+  {
+    LOG_STDERR ("A Mode S message must have %d or %d hex digits, not %d.\n",
+                2*MODES_SHORT_MSG_BYTES, 2*MODES_LONG_MSG_BYTES, len);
+    return (1);
+  }
+
+  memset (bin_msg, '\0', sizeof(bin_msg));
+  for (j = 0; j < len; j += 2)
+      bin_msg [j/2] = (hex_digit_val(hex[j]) << 4) | hex_digit_val (hex[j+1]);
+
+  decode_modeS_message (&mm, bin_msg);
+  if (mm.msg_bits != 4*len)
+     LOG_STDOUT ("Warning: DF %d should have %d bits, not %d.\n", mm.msg_type, mm.msg_bits, 4*len);
+  display_modeS_message (&mm);
+  return (0);
+}
+
+#if defined(USE_TESTS)
+/*
+ * The self-tests for option `--test`.
+ * Only built with `USE_TESTS = 1` in `Makefile.Windows`.
+ */
+static int num_tests;
+
+/**
+ * Decode a 112 bit test message in `hex` into `mm`.
+ */
+static void test_decode (const char *hex, modeS_message *mm)
+{
+  uint8_t bin_msg [MODES_LONG_MSG_BYTES];
+  int     j;
+
+  for (j = 0; j < 2*MODES_LONG_MSG_BYTES; j += 2)
+      bin_msg [j/2] = (hex_digit_val(hex[j]) << 4) | hex_digit_val (hex[j+1]);
+  decode_modeS_message (mm, bin_msg);
+}
+
+/**
+ * Compare a decoded position against the expected `lat` and `lon`.
+ *
+ * \retval 0 if `pos` is within 0.0001 degrees (approx. 11 m) of the expected position.
+ * \retval 1 otherwise.
+ */
+static int test_position (const char *what, pos_t pos, double lat, double lon)
+{
+  bool ok = (fabs(pos.lat - lat) < 0.0001 && fabs(pos.lon - lon) < 0.0001);
+
+  num_tests++;
+  LOG_STDOUT ("  %-30s: %9.5f, %10.5f (expected %9.5f, %10.5f): %s\n",
+              what, pos.lat, pos.lon, lat, lon, ok ? "OK" : "FAILED");
+  return (ok ? 0 : 1);
+}
+
+/**
+ * Check that a position was rejected and that `Modes.stat.CPR_range_errors`
+ * is now `range_errors`.
+ *
+ * \retval 0 if `pos` was not set and the count is right.
+ * \retval 1 otherwise.
+ */
+static int test_rejected (const char *what, pos_t pos, uint64_t range_errors)
+{
+  bool ok = (!VALID_POS(pos) && Modes.stat.CPR_range_errors == range_errors);
+
+  num_tests++;
+  LOG_STDOUT ("  %-30s: %s\n", what, ok ? "rejected, OK" : "FAILED");
+  return (ok ? 0 : 1);
+}
+
+/**
+ * Do a global CPR decode from the raw even and odd positions.
+ * `odd_last` tells which message was received last.
+ */
+static pos_t test_CPR_global (int even_lat, int even_lon, int odd_lat, int odd_lon, bool odd_last)
+{
+  aircraft a;
+
+  memset (&a, '\0', sizeof(a));
+  a.even_CPR_lat  = even_lat;
+  a.even_CPR_lon  = even_lon;
+  a.even_CPR_time = odd_last ? 1 : 2;
+  a.odd_CPR_lat   = odd_lat;
+  a.odd_CPR_lon   = odd_lon;
+  a.odd_CPR_time  = odd_last ? 2 : 1;
+  decode_CPR (&a);
+  return (a.position);
+}
+
+/**
+ * Do a local CPR decode of a raw position relative to `ref_lat` and `ref_lon`.
+ */
+static pos_t test_CPR_local (double ref_lat, double ref_lon, int raw_lat, int raw_lon, bool odd, bool surface)
+{
+  pos_t ref, pos;
+
+  ref.lat = ref_lat;
+  ref.lon = ref_lon;
+  memset (&pos, '\0', sizeof(pos));
+  decode_CPR_relative (ref, raw_lat, raw_lon, odd, surface, &pos);
+  return (pos);
+}
+
+/**
+ * Do a local CPR decode of a raw position relative to our home position.
+ * As done for a received message; with the range checks.
+ */
+static pos_t test_CPR_home (int raw_lat, int raw_lon, bool odd, bool surface)
+{
+  aircraft      a;
+  modeS_message mm;
+
+  memset (&a, '\0', sizeof(a));
+  memset (&mm, '\0', sizeof(mm));
+  mm.raw_latitude  = raw_lat;
+  mm.raw_longitude = raw_lon;
+  mm.odd_flag      = odd;
+  decode_CPR_local (&a, &mm, surface);
+  return (a.position);
+}
+
+static void test_set_home (double lat, double lon)
+{
+  Modes.home_pos.lat = lat;
+  Modes.home_pos.lon = lon;
+  Modes.home_pos_ok  = (lat != 0.0 || lon != 0.0);
+}
+
+/**
+ * The brute-force CRC correction of 1 bit errors used before the syndrome tables.
+ * Flip every bit and recompute the checksum.
+ * Used as a reference for `test_syndromes()`.
+ */
+static int test_brute_force_fix_1 (uint8_t *msg, int bits)
+{
+  uint8_t aux [MODES_LONG_MSG_BYTES];
+  int     i;
+
+  for (i = 0; i < bits; i++)
+  {
+    memcpy (aux, msg, bits/8);
+    aux [i / 8] ^= 1 << (7 - (i % 8));    /* Flip i-th bit. */
+
+    if (modeS_checksum(aux, bits) == (((uint32_t)aux[(bits/8)-3] << 16) |
+                                      ((uint32_t)aux[(bits/8)-2] << 8) |
+                                       (uint32_t)aux[(bits/8)-1]))
+    {
+      memcpy (msg, aux, bits/8);
+      return (i);
+    }
+  }
+  return (-1);
+}
+
+/**
+ * As `test_brute_force_fix_1()`, but for every possible two bit combination.
+ */
+static int test_brute_force_fix_2 (uint8_t *msg, int bits)
+{
+  uint8_t aux [MODES_LONG_MSG_BYTES];
+  int     i, j;
+
+  for (j = 0; j < bits; j++)
+      for (i = j+1; i < bits; i++)
+      {
+        memcpy (aux, msg, bits/8);
+        aux [j / 8] ^= 1 << (7 - (j % 8));    /* Flip j-th bit. */
+        aux [i / 8] ^= 1 << (7 - (i % 8));    /* Flip i-th bit. */
+
+        if (modeS_checksum(aux, bits) == (((uint32_t)aux[(bits/8)-3] << 16) |
+                                          ((uint32_t)aux[(bits/8)-2] << 8) |
+                                           (uint32_t)aux[(bits/8)-1]))
+        {
+          memcpy (msg, aux, bits/8);
+          return (j | (i << 8));
+        }
+      }
+  return (-1);
+}
+
+static uint64_t test_usec_now (void)
+{
+  struct timeval now;
+
+  _gettimeofday (&now, NULL);
+  return ((uint64_t)now.tv_sec * 1000000 + now.tv_usec);
+}
+
+#define TEST_SYNDROME_MSGS 1000
+
+/**
+ * Compare and benchmark the syndrome table lookup against the brute-force
+ * CRC correction for 1 and 2 bit errors in 56 and 112 bit messages.
+ *
+ * Each case uses `TEST_SYNDROME_MSGS` random messages with a correct CRC
+ * and 1 or 2 flipped bits. Every 10th message gets some extra damage
+ * that can not be corrected. Both methods must return the same bit
+ * position(s) and the same fixed message.
+ *
+ * \retval the number of failed tests.
+ */
+static int test_syndromes (void)
+{
+  static uint8_t msg_brute [TEST_SYNDROME_MSGS][MODES_LONG_MSG_BYTES];
+  static uint8_t msg_table [TEST_SYNDROME_MSGS][MODES_LONG_MSG_BYTES];
+  static int     rc_brute  [TEST_SYNDROME_MSGS];
+  static int     rc_table  [TEST_SYNDROME_MSGS];
+  int      bits, err_bits, i, j, failed = 0;
+  uint64_t start, usec_brute, usec_table;
+
+  num_tests++;
+  if (!syndromes_1_ok || !syndromes_2_ok)
+  {
+    LOG_STDOUT ("  Duplicate syndromes: FAILED\n");
+    failed++;
+  }
+
+  srand (1);
+
+  for (err_bits = 1; err_bits <= 2; err_bits++)
+      for (bits = MODES_SHORT_MSG_BITS; bits <= MODES_LONG_MSG_BITS; bits += MODES_SHORT_MSG_BITS)
+      {
+        int mismatch = 0, fixed = 0;
+
+        for (i = 0; i < TEST_SYNDROME_MSGS; i++)
+        {
+          uint8_t *msg = msg_brute [i];
+          uint32_t crc;
+          int      bit1, bit2;
+
+          for (j = 0; j < bits/8; j++)
+              msg [j] = (uint8_t) rand();
+
+          crc = modeS_checksum (msg, bits);
+          msg [(bits/8)-3] = (uint8_t) (crc >> 16);
+          msg [(bits/8)-2] = (uint8_t) (crc >> 8);
+          msg [(bits/8)-1] = (uint8_t) crc;
+
+          bit1 = rand() % bits;
+          msg [bit1 / 8] ^= 1 << (7 - (bit1 % 8));
+          if (err_bits == 2)
+          {
+            do
+              bit2 = rand() % bits;
+            while (bit2 == bit1);
+            msg [bit2 / 8] ^= 1 << (7 - (bit2 % 8));
+          }
+          if (i % 10 == 0)
+             msg [2] ^= 0x55;
+          memcpy (msg_table[i], msg, sizeof(msg_table[i]));
+        }
+
+        start = test_usec_now();
+        for (i = 0; i < TEST_SYNDROME_MSGS; i++)
+            rc_brute [i] = (err_bits == 1) ? test_brute_force_fix_1 (msg_brute[i], bits) :
+                                             test_brute_force_fix_2 (msg_brute[i], bits);
+        usec_brute = test_usec_now() - start;
+
+        start = test_usec_now();
+        for (i = 0; i < TEST_SYNDROME_MSGS; i++)
+            rc_table [i] = (err_bits == 1) ? fix_single_bit_errors (msg_table[i], bits) :
+                                             fix_two_bits_errors (msg_table[i], bits);
+        usec_table = test_usec_now() - start;
+
+        for (i = 0; i < TEST_SYNDROME_MSGS; i++)
+        {
+          if (rc_brute[i] != rc_table[i] || memcmp(msg_brute[i], msg_table[i], bits/8))
+             mismatch++;
+          if (rc_table[i] != -1)
+             fixed++;
+        }
+
+        num_tests++;
+        LOG_STDOUT ("  %d bit error(s), %3d bits: brute-force %8.2f us, table %5.2f us per message, %d of %d fixed: %s\n",
+                    err_bits, bits, (double)usec_brute / TEST_SYNDROME_MSGS, (double)usec_table / TEST_SYNDROME_MSGS,
+                    fixed, TEST_SYNDROME_MSGS, mismatch ? "FAILED" : "OK");
+        if (mismatch)
+           failed++;
+      }
+  return (failed);
+}
+
+/**
+ * Check the CPR decoding and the CRC error correction with option `--test`.
+ *
+ * The first messages and positions are the examples from *"The 1090 MHz Riddle"*
+ * and the `pyModeS` test-suite: <br>
+ * https://mode-s.org/decode/content/ads-b/3-airborne-position.html
+ *
+ * The other raw CPR positions are encoded from the expected positions as in
+ * DO-260B, appendix A. They cover both hemispheres, the +/-180 degree meridian,
+ * a latitude zone change between an even and odd message and the range checks.
+ *
+ * The home position, the range outline and the statistics are restored afterwards.
+ *
+ * Then `test_syndromes()` compares and benchmarks the CRC error correction.
+ *
+ * \retval 0 if all tests passed.
+ * \retval 1 if any test failed.
+ */
+int modeS_tests (void)
+{
+  modeS_message mm;
+  pos_t         pos;
+  pos_t         home_pos    = Modes.home_pos;
+  bool          home_pos_ok = Modes.home_pos_ok;
+  statistics    stat        = Modes.stat;
+  range_point   range_outline [MODES_RANGE_BEARINGS];
+  int           even_lat, even_lon, failed = 0;
+
+  /* `decode_CPR()` updates the range outline via `set_home_distance()`.
+   */
+  memcpy (range_outline, Modes.range_outline, sizeof(range_outline));
+
+  num_tests = 0;
+  test_set_home (0.0, 0.0);
+
+  LOG_STDOUT ("Global CPR decoding:\n");
+
+  test_decode ("8D40621D58C382D690C8AC2863A7", &mm);   /* even */
+  even_lat = mm.raw_latitude;
+  even_lon = mm.raw_longitude;
+  test_decode ("8D40621D58C386435CC412692AD6", &mm);   /* odd */
+
+  pos = test_CPR_global (even_lat, even_lon, mm.raw_latitude, mm.raw_longitude, false);
+  failed += test_position ("Even last", pos, 52.25720, 3.91937);
+
+  pos = test_CPR_global (even_lat, even_lon, mm.raw_latitude, mm.raw_longitude, true);
+  failed += test_position ("Odd last", pos, 52.26578, 3.93891);
+
+  pos = test_CPR_global (30502, 7028, 43101, 28284, false);
+  failed += test_position ("South-West, even last", pos, -34.60372, -58.38159);
+
+  pos = test_CPR_global (30502, 7028, 43101, 28284, true);
+  failed += test_position ("South-West, odd last", pos, -34.60372, -58.38159);
+
+  pos = test_CPR_global (101391, 101939, 86594, 128801, true);
+  failed += test_position ("North-West, odd last", pos, 40.64131, -73.77814);
+
+  pos = test_CPR_global (5461, 65432, 11924, 130970, false);
+  failed += test_position ("West of 180 deg", pos, -17.75000, 179.99500);
+
+  pos = test_CPR_global (5461, 65640, 11924, 102, true);
+  failed += test_position ("East of 180 deg", pos, -17.75000, -179.99500);
+
+  /* Even at 10.46 deg (NL 59) and odd at 10.48 deg (NL 58).
+   */
+  pos = test_CPR_global (97430, 36409, 94051, 21845, true);
+  failed += test_rejected ("Latitude zone changed", pos, stat.CPR_range_errors);
+
+  LOG_STDOUT ("Local airborne CPR decoding:\n");
+
+  test_decode ("8D40621D58C382D690C8AC2863A7", &mm);
+  pos = test_CPR_local (52.258, 3.918, mm.raw_latitude, mm.raw_longitude, mm.odd_flag != 0, false);
+  failed += test_position ("North-East, even", pos, 52.25720, 3.91937);
+
+  pos = test_CPR_local (-33.5, -57.5, 43101, 28284, true, false);
+  failed += test_position ("South-West, odd", pos, -34.60372, -58.38159);
+
+  pos = test_CPR_local (-17.5, -179.9, 11924, 130970, true, false);
+  failed += test_position ("Across 180 deg", pos, -17.75000, 179.99500);
+
+  LOG_STDOUT ("Local surface CPR decoding:\n");
+
+  test_decode ("8FC8200A3AB8F5F893096B000000", &mm);
+  pos = test_CPR_local (-43.5, 172.5, mm.raw_latitude, mm.raw_longitude, mm.odd_flag != 0, true);
+  failed += test_position ("South-East, odd message", pos, -43.48564, 172.53942);
+
+  pos = test_CPR_local (-34.0, 151.0, 97837, 82294, true, true);
+  failed += test_position ("South-East, odd", pos, -33.94611, 151.17722);
+
+  pos = test_CPR_local (52.0, 4.5, 114288, 118693, false, true);
+  failed += test_position ("North-East, even", pos, 52.30792, 4.76389);
+
+  pos = test_CPR_local (40.5, -73.5, 84232, 121987, true, true);
+  failed += test_position ("North-West, odd", pos, 40.64131, -73.77814);
+
+  pos = test_CPR_local (-34.5, -58.5, 102916, 17104, false, true);
+  failed += test_position ("South-West, even", pos, -34.82222, -58.53583);
+
+  LOG_STDOUT ("Range checks:\n");
+
+  /* Amsterdam from New York is more than `MODES_MAX_RANGE`.
+   */
+  test_set_home (40.64131, -73.77814);
+  test_decode ("8D40621D58C386435CC412692AD6", &mm);
+  pos = test_CPR_global (even_lat, even_lon, mm.raw_latitude, mm.raw_longitude, false);
+  failed += test_rejected ("Global, > MODES_MAX_RANGE", pos, stat.CPR_range_errors + 1);
+
+  /* 54.5, 8.5 is 220 Nm from 52.0, 4.0.
+   */
+  test_set_home (52.0, 4.0);
+  pos = test_CPR_home (10923, 105222, false, false);
+  failed += test_rejected ("Airborne, > LOCAL_RANGE_AIR", pos, stat.CPR_range_errors + 2);
+
+  pos = test_CPR_home (93000, 51372, false, false);
+  failed += test_position ("Airborne, < LOCAL_RANGE_AIR", pos, 52.25720, 3.91937);
+
+  /* 52.6, 5.1 is 54 Nm from 52.0, 4.0.
+   */
+  pos = test_CPR_home (8738, 5243, false, true);
+  failed += test_rejected ("Surface, > LOCAL_RANGE_SURFACE", pos, stat.CPR_range_errors + 3);
+
+  pos = test_CPR_home (114288, 118693, false, true);
+  failed += test_position ("Surface, < LOCAL_RANGE_SURFACE", pos, 52.30792, 4.76389);
 
-  *p++ = '\n';
-  connection_send (MODES_NET_SERVICE_SBS_OUT, msg, p - msg);
-}
+  Modes.home_pos    = home_pos;
+  Modes.home_pos_ok = home_pos_ok;
+  Modes.stat        = stat;
+  memcpy (Modes.range_outline, range_outline, sizeof(Modes.range_outline));
 
-/**
- * Turn an hex digit into its 4 bit decimal value.
- * Returns -1 if the digit is not in the 0-F range.
- */
-int hex_digit_val (int c)
-{
-  c = tolower (c);
-  if (c >= '0' && c <= '9')
-     return (c - '0');
-  if (c >= 'a' && c <= 'f')
-     return (c - 'a' + 10);
-  return (-1);
+  LOG_STDOUT ("CRC error correction:\n");
+  failed += test_syndromes();
+
+  LOG_STDOUT ("%d of %d tests failed.\n", failed, num_tests);
+  return (failed ? 1 : 0);
 }
+#endif  /* USE_TESTS */
 
 /**
- * This function decodes a string representing a Mode S message in
- * raw hex format like: `*8D4B969699155600E87406F5B69F;<eol>`
+ * Decode a NUL-terminated SBS `MSG` record in `msg` and update the aircraft it refers to.
+ * The 22 fields are: <br>
+ * `MSG,type,session-id,aircraft-id,hex-ident,flight-id,date-gen,time-gen,date-log,time-log,`
+ * `callsign,altitude,speed,track,lat,lon,vert-rate,squawk,alert,emergency,SPI,on-ground`
  *
- * The string is supposed to be at the start of the client buffer
- * and NUL-terminated. It accepts both '\n' and '\r\n' terminated records.
+ * An empty field is not updated since a SBS source sends only the fields
+ * carried by the transmission type.
  *
- * The message is passed to the higher level layers, so it feeds
- * the selected screen output, the network output and so forth.
+ * Ref: http://woodair.net/sbs/article/barebones42_socket_data.htm
  *
- * If the message looks invalid, it is silently discarded.
+ * \retval 1 if the record was used.
+ * \retval 0 if it's not a legal `MSG` record.
  */
-void decode_hex_message (mg_iobuf *msg, int loop_cnt)
+int modeS_recv_SBS_input (mg_iobuf *msg, modeS_message *mm)
 {
-  modeS_message mm;
-  uint8_t       bin_msg [MODES_LONG_MSG_BYTES];
-  int           len, j;
-  uint8_t      *hex;
-  uint8_t      *end = memchr (msg->buf, '\n', msg->len);
+  char     *fields [22];
+  char     *p = (char*) msg->buf;
+  char     *end;
+  unsigned  num;
+  uint32_t  addr;
+  aircraft *a;
+  uint64_t  now = MSEC_TIME();
 
-  if (!end)
+  memset (mm, '\0', sizeof(*mm));
+
+  for (num = 0; p && num < DIM(fields); num++)
   {
-    if (!Modes.interactive)
-       LOG_STDOUT ("RAW(%d): Bogus msg: '%.*s'...\n", loop_cnt, (int)msg->len, msg->buf);
-    Modes.stat.unrecognized_raw++;
-    mg_iobuf_del (msg, 0, msg->len);
-    return;
+    fields [num] = p;
+    p = strchr (p, ',');
+    if (p)
+       *p++ = '\0';
   }
 
-  *end++ = '\0';
-  if (end[-2] == '\r')
-     end[-2] = '\0';
+  if (num != DIM(fields) || p)     /* Too few or too many fields */
+     return (0);
 
-  /* Remove spaces on the left and on the right.
-   */
-  hex = msg->buf;
-  len = end - msg->buf - 1;
-  while (len && isspace(hex[len-1]))
-  {
-    hex[len-1] = '\0';
-    len--;
-  }
-  while (isspace(*hex))
-  {
-    hex++;
-    len--;
-  }
+  if (strlen(fields[1]) != 1 || fields[1][0] < '1' || fields[1][0] > '8')
+     return (0);
 
-  /* Check it's format.
-   */
-  if (len < 2)
+  addr = strtoul (fields[4], &end, 16);
+  if (*end || strlen(fields[4]) != 6)
+     return (0);
+
+  mm->AA1    = addr >> 16;
+  mm->AA2    = (addr >> 8) & 0xFF;
+  mm->AA3    = addr & 0xFF;
+  mm->CRC_ok = true;
+
+  a = aircraft_find (addr);
+  if (!a)
   {
-    Modes.stat.empty_raw++;
-    mg_iobuf_del (msg, 0, end - msg->buf);
-    return;
+    a = aircraft_create (addr, now);
+    if (!a)
+       return (0);
+    LIST_ADD_HEAD (aircraft, &Modes.aircrafts, a);
   }
-  if (hex[0] != '*' || !memchr(msg->buf, ';', len))
+  a->seen_last = now;
+  a->msg_time  = now;
+  a->messages++;
+
+  if (*fields[10])
   {
-    Modes.stat.unrecognized_raw++;
-    mg_iobuf_del (msg, 0, end - msg->buf);
-    return;
+    size_t len = strlen (fields[10]);
+
+    /* Drop the trailing spaces. A SBS source pads the callsign to 8 characters
+     * like in the identification message.
+     */
+    while (len > 0 && fields[10][len-1] == ' ')
+       len--;
+    if (len > sizeof(mm->flight)-1)
+       len = sizeof(mm->flight)-1;
+    if (len > 0)
+    {
+      memcpy (mm->flight, fields[10], len);
+      mm->flight [len] = '\0';
+      memcpy (a->flight, mm->flight, sizeof(a->flight));
+    }
   }
+  if (*fields[11])
+     a->altitude = mm->altitude = atoi (fields[11]);
 
-  /* Turn the message into binary.
-   */
-  hex++;   /* Skip `*` and `;` */
-  len -= 2;
-  if (len > 2*MODES_LONG_MSG_BYTES)   /* Too long message... broken. */
+  if (*fields[12])
+     a->speed = mm->velocity = atoi (fields[12]);
+
+  if (*fields[13])
   {
-    Modes.stat.unrecognized_raw++;
-    mg_iobuf_del (msg, 0, end - msg->buf);
-    return;
+    a->heading = mm->heading = atoi (fields[13]);
+    a->heading_is_valid = mm->heading_is_valid = true;
   }
-
-  for (j = 0; j < len; j += 2)
+  if (*fields[14] && *fields[15])
   {
-    int high = hex_digit_val (hex[j]);
-    int low  = hex_digit_val (hex[j+1]);
+    pos_t pos;
 
-    if (high == -1 || low == -1)
+    pos.lat = atof (fields[14]);
+    pos.lon = atof (fields[15]);
+    if (VALID_POS(pos) && position_plausible(a, pos))
     {
-      Modes.stat.unrecognized_raw++;
-      mg_iobuf_del (msg, 0, end - msg->buf);
-      return;
+      a->position = pos;
+      set_home_distance (a);
     }
-    bin_msg[j/2] = (high << 4) | low;
   }
-  mg_iobuf_del (msg, 0, end - msg->buf);
-  Modes.stat.good_raw++;
-  decode_modeS_message (&mm, bin_msg);
-  modeS_user_message (&mm);
-}
-
-/**
- * \todo
- * Ref: http://woodair.net/sbs/article/barebones42_socket_data.htm
- */
-int modeS_recv_SBS_input (mg_iobuf *msg, modeS_message *mm)
-{
-  memset (mm, '\0', sizeof(*mm));
-
-//decode 'msg' and fill 'mm'
-//modeS_user_message (&mm);
-  MODES_NOTUSED (msg);
-  return (0);
+  if (*fields[17])
+     a->identity = mm->identity = atoi (fields[17]);
+  return (1);
 }
 
 /**
@@ -4719,8 +7199,9 @@ void decode_SBS_message (mg_iobuf *msg, int loop_cnt)
 
   if (!strncmp((char*)msg->buf, "MSG,", 4))
   {
-    modeS_recv_SBS_input (msg, &mm);
-    Modes.stat.good_SBS++;
+    if (modeS_recv_SBS_input(msg, &mm))
+         Modes.stat.good_SBS++;
+    else Modes.stat.unrecognized_SBS++;
   }
   mg_iobuf_del (msg, 0, end - msg->buf);
 }
@@ -4791,6 +7272,7 @@ void show_help (const char *fmt, ...)
           "    --database <file>        The CSV file for the aircraft database\n"
           "                             (default: \"%s\").\n"
           "    --debug <flags>          Debug mode; see below for details.\n"
+          "    --decode <hex>           Decode and show a single Mode S message, then exit.\n"
           "    --infile <filename>      Read data from file (use `-' for stdin).\n"
           "    --interactive            Interactive mode refreshing data on screen.\n"
           "    --interactive-rows <num> Max number of rows in interactive mode (default: 15).\n"
@@ -4799,12 +7281,20 @@ void show_help (const char *fmt, ...)
           "    --loop <N>               With --infile, read the file in a loop <N> times (default: 2^63).\n"
           "    --max-messages <N>       Max number of messages to process (default: Inf).\n"
           "    --metric                 Use metric units (meters, km/h, ...).\n"
+          "    --modeac                 Detect Mode A/C replies too.\n"
           "    --no-fix                 Disable single-bits error correction using CRC.\n"
           "    --no-crc-check           Disable checking CRC of messages (discouraged).\n"
           "    --only-addr              Show only ICAO addresses (testing purposes).\n"
+          "    --pos-report <file>      Write the positions rejected by the range and speed checks to a CSV <file>.\n"
+          "    --rate-csv <file>        Write the messages, aircrafts and DFs per minute to a CSV <file>.\n"
           "    --raw                    Show only the raw Mode-S hex message.\n"
+          "    --record <file>          Record the raw IQ samples from the device to <file>.\n"
+          "    --record-time <sec>      Stop after recording <sec> seconds (default: no limit).\n"
           "    --silent                 Silent mode for testing network I/O (together with '--debug n').\n"
           "    --strip <level>          Strip IQ file removing samples below level.\n"
+#if defined(USE_TESTS)
+          "    --test                   Check the CPR decoding against known messages, then exit.\n"
+#endif
           "    -h, --help               Show this help.\n\n",
           Modes.who_am_I, Modes.aircraft_db, MODES_INTERACTIVE_TTL/1000);
 
@@ -4815,13 +7305,21 @@ void show_help (const char *fmt, ...)
           "    --net-http-port <port>   HTTP server port (default: %u).\n"
           "    --net-ri-port <port>     TCP listening port for raw input  (default: %u).\n"
           "    --net-ro-port <port>     TCP listening port for raw output (default: %u).\n"
+          "    --net-ro-mlat            Add a MLAT timestamp (and signal-level) to the raw output.\n"
           "    --net-sbs-port <port>    TCP listening port for SBS output (default: %u).\n"
+          "                             Each <port> can be given as <addr:port> to listen on <addr> only.\n"
+          "                             E.g. `--net-ri-port 127.0.0.1:30001`.\n"
           "    --host-raw <addr:port>   Remote host/port for raw input with `--net-active`.\n"
           "    --host-sbs <addr:port>   Remote host/port for SBS input with `--net-active`.\n"
+          "    --web-auth <user:pass>   Require Basic authentication from HTTP clients.\n"
+          "                             A <token> without a ':' requires a `Bearer` token instead.\n"
+          "                             At most %d characters; no spaces, '\"', ',', ';' or '\\' in a <token>.\n"
+          "                             From a browser, open the Web-page once with `?access_token=<token>`.\n"
+          "                             `--web-auth random` generates a token and prints it on startup.\n"
           "    --web-page <file>        The Web-page to serve for HTTP clients\n"
           "                             (default: \"%s\\%s\").\n\n",
           MODES_NET_PORT_HTTP, MODES_NET_PORT_RAW_IN, MODES_NET_PORT_RAW_OUT,
-          MODES_NET_PORT_SBS, Modes.web_root, Modes.web_page);
+          MODES_NET_PORT_SBS, MODES_WEB_AUTH_MAX, Modes.web_root, Modes.web_page);
 
   printf ("  RTLSDR / SDRplay options:\n"
           "    --agc                    Enable Digital AGC              (default: off)\n"
@@ -4885,6 +7383,9 @@ void background_tasks (void)
 
   remove_stale_aircrafts (now);
 
+  if (Modes.net)
+     net_rates_update (now);
+
   /* Refresh screen and console-title when in interactive mode
    */
   if (Modes.interactive)
@@ -4945,6 +7446,7 @@ void show_connection_stats (void)
 {
   const char *cli_srv = (Modes.net_active ? "server" : "client");
   uint64_t    sum;
+  double      elapsed = (double) (MSEC_TIME() - Modes.net_start) / 1000.0;
   int         s;
 
   LOG_STDOUT ("\nNetwork statistics:\n");
@@ -4961,7 +7463,14 @@ void show_connection_stats (void)
         continue;
       }
       LOG_STDOUT ("    %8llu HTTP GET requests received.\n", Modes.stat.HTTP_get_requests);
+      LOG_STDOUT ("    %8llu JSON requests received.\n", Modes.stat.HTTP_JSON_requests);
+      if (elapsed >= 1.0 && Modes.stat.HTTP_JSON_requests > 0)
+         LOG_STDOUT ("    %8.2f JSON requests/s (approx. %.1f kB per request).\n",
+                     (double) Modes.stat.HTTP_JSON_requests / elapsed,
+                     (double) Modes.stat.HTTP_JSON_bytes / (1024.0 * Modes.stat.HTTP_JSON_requests));
       LOG_STDOUT ("    %8llu HTTP 404 replies sent.\n", Modes.stat.HTTP_404_responses);
+      if (Modes.web_auth)
+         LOG_STDOUT ("    %8llu HTTP 401 replies sent.\n", Modes.stat.HTTP_401_responses);
       LOG_STDOUT ("    %8llu HTTP/WebSocket upgrades.\n", Modes.stat.HTTP_websockets);
       LOG_STDOUT ("    %8llu server connection \"keep-alive\".\n", Modes.stat.HTTP_keep_alive_sent);
       LOG_STDOUT ("    %8llu client connection \"keep-alive\".\n", Modes.stat.HTTP_keep_alive_recv);
@@ -4994,6 +7503,16 @@ void show_connection_stats (void)
 
     LOG_STDOUT ("    %8llu bytes sent.\n", Modes.stat.bytes_sent[s]);
     LOG_STDOUT ("    %8llu bytes recv.\n", Modes.stat.bytes_recv[s]);
+
+    /* Estimate the bandwidth from the traffic so far. Useful
+     * to decide which services to enable on a metered connection.
+     */
+    if (elapsed >= 1.0 && Modes.stat.bytes_sent[s] > 0)
+    {
+      double rate = (double) Modes.stat.bytes_sent[s] / elapsed;
+
+      LOG_STDOUT ("    %8.2f kB/s sent (approx. %.1f MB/day).\n", rate / 1024.0, rate * 86400.0 / (1024.0 * 1024.0));
+    }
     LOG_STDOUT ("    %8u %s now.\n", *handler_num_connections(s), cli_srv);
   }
 }
@@ -5026,6 +7545,16 @@ void show_statistics (void)
     LOG_STDOUT (" %8llu unique aircrafts.\n", Modes.stat.unique_aircrafts);
     LOG_STDOUT (" %8llu unique aircrafts from CSV.\n", Modes.stat.unique_aircrafts_CSV);
     LOG_STDOUT (" %8llu unrecognized ME types.\n", Modes.stat.unrecognized_ME);
+    LOG_STDOUT (" %8llu global CPR positions decoded.\n", Modes.stat.CPR_global_ok);
+    LOG_STDOUT (" %8llu local CPR positions decoded.\n", Modes.stat.CPR_local_ok);
+    LOG_STDOUT (" %8llu CPR positions out of range.\n", Modes.stat.CPR_range_errors);
+    LOG_STDOUT (" %8llu positions with an impossible speed.\n", Modes.stat.CPR_speed_errors);
+    LOG_STDOUT (" %8llu suspect aircrafts (ghosts or duplicate addresses).\n", Modes.stat.suspect_aircrafts);
+    show_DF_statistics();
+    if (Modes.mode_AC)
+       show_AC_statistics();
+    if (Modes.home_pos_ok)
+       show_RSSI_statistics();
   }
   if (Modes.net)
      show_connection_stats();
@@ -5033,6 +7562,160 @@ void show_statistics (void)
      show_raw_SBS_stats();
 }
 
+/**
+ * Print the distribution of Downlink Formats with the CRC pass rate
+ * and number of corrected messages for each.
+ * A low pass rate often means a too high gain or interference.
+ */
+void show_DF_statistics (void)
+{
+  int i;
+
+  LOG_STDOUT ("Downlink Format statistics:\n");
+  LOG_STDOUT ("   DF     total   CRC okay    fixed\n");
+  for (i = 0; i < DIM(Modes.stat.DF_total); i++)
+  {
+    uint64_t total = Modes.stat.DF_total [i];
+
+    if (total == 0)
+       continue;
+    LOG_STDOUT ("   %2d  %8llu  %8llu (%5.1f%%)  %8llu\n", i, total, Modes.stat.DF_CRC_ok[i],
+                100.0 * (double)Modes.stat.DF_CRC_ok[i] / (double)total, Modes.stat.DF_fixed[i]);
+  }
+}
+
+/**
+ * Return true if a Mode S aircraft has the squawk of `Modes.stat.AC_squawks [idx]`.
+ */
+static bool squawk_is_modeS (int idx)
+{
+  const aircraft *a;
+  int   identity = 1000*((idx >> 9) & 7) + 100*((idx >> 6) & 7) + 10*((idx >> 3) & 7) + (idx & 7);
+
+  for (a = Modes.aircrafts; a; a = a->next)
+      if (a->identity == identity)
+         return (true);
+  return (false);
+}
+
+/**
+ * Print the number of Mode A/C replies and the most seen squawks.
+ * A squawk not used by any Mode S aircraft is from a squawk-only target;
+ * an aircraft with only a Mode A/C transponder. Or from a Mode S
+ * aircraft that has not sent its identity yet.
+ */
+void show_AC_statistics (void)
+{
+  static uint64_t counts [DIM(Modes.stat.AC_squawks)];
+  uint64_t        unique = 0, squawk_only = 0;
+  int             i, n;
+
+  memcpy (counts, Modes.stat.AC_squawks, sizeof(counts));
+  for (i = 0; i < DIM(counts); i++)
+  {
+    if (counts[i] == 0)
+       continue;
+    unique++;
+    if (!squawk_is_modeS(i))
+       squawk_only++;
+  }
+
+  LOG_STDOUT ("Mode A/C statistics:\n");
+  LOG_STDOUT (" %8llu replies.\n", Modes.stat.AC_replies);
+  LOG_STDOUT (" %8llu with the squawk of a Mode S aircraft.\n", Modes.stat.AC_matched);
+  LOG_STDOUT (" %8llu unique squawks, %llu of these squawk-only.\n", unique, squawk_only);
+  if (unique == 0)
+     return;
+
+  LOG_STDOUT ("   squawk   replies  Mode S\n");
+  for (n = 0; n < MODES_AC_SHOW_SQUAWKS; n++)
+  {
+    int max = 0;
+
+    for (i = 1; i < DIM(counts); i++)
+        if (counts[i] > counts[max])
+           max = i;
+    if (counts[max] == 0)
+       break;
+
+    LOG_STDOUT ("    %04o  %8llu  %s\n", max, counts[max], squawk_is_modeS(max) ? "yes" : "no");
+    counts [max] = 0;
+  }
+}
+
+/**
+ * Print the RSSI per bearing sector from home, normalised to 1 Nm.
+ * Report the sectors that are much weaker than the average of all sectors.
+ * This can point to an antenna problem or something blocking the view in
+ * these directions.
+ */
+void show_RSSI_statistics (void)
+{
+  const RSSI_sector *rs;
+  double             avg, sum = 0.0;
+  uint64_t           num = 0;
+  int                i, weak = 0;
+
+  for (i = 0; i < MODES_RSSI_SECTORS; i++)
+  {
+    rs = &Modes.stat.RSSI_sectors [i];
+    if (rs->num >= MODES_RSSI_MIN_MESSAGES)
+    {
+      sum += rs->dB_sum;
+      num += rs->num;
+    }
+  }
+  if (num == 0)
+     return;
+
+  avg = sum / (double) num;
+
+  LOG_STDOUT ("RSSI statistics per bearing sector (normalised to 1 Nm, average %.1lf dBFS):\n", avg);
+  LOG_STDOUT ("   bearing    messages  avg. dist     RSSI\n");
+  for (i = 0; i < MODES_RSSI_SECTORS; i++)
+  {
+    double sector_avg;
+    bool   is_weak;
+
+    rs = &Modes.stat.RSSI_sectors [i];
+    if (rs->num == 0)
+       continue;
+
+    sector_avg = rs->dB_sum / (double) rs->num;
+    is_weak    = (rs->num >= MODES_RSSI_MIN_MESSAGES && sector_avg < avg - MODES_RSSI_WEAK_DB);
+    if (is_weak)
+       weak++;
+
+    LOG_STDOUT ("   %5.1lf - %5.1lf  %8llu  %6.1lf %s  %6.1lf%s\n",
+                i * 360.0 / MODES_RSSI_SECTORS, (i + 1) * 360.0 / MODES_RSSI_SECTORS, rs->num,
+                rs->dist_sum / (double) rs->num / (Modes.metric ? 1000.0 : 1852.0), Modes.metric ? "km" : "Nm",
+                sector_avg, is_weak ? "  weak" : rs->num < MODES_RSSI_MIN_MESSAGES ? "  (too few)" : "");
+  }
+  if (weak > 0)
+       LOG_STDOUT ("   %d weak sector(s). Check the antenna and what is blocking it in these directions.\n", weak);
+  else LOG_STDOUT ("   No weak sectors.\n");
+}
+
+/**
+ * Print a summary of this session on exit.
+ */
+void show_session_summary (void)
+{
+  uint64_t sec = (MSEC_TIME() - Modes.start_time) / 1000;
+
+  LOG_STDOUT ("\nSession summary:\n");
+  LOG_STDOUT (" %02llu:%02llu:%02llu duration (hh:mm:ss).\n", sec / 3600, (sec / 60) % 60, sec % 60);
+  LOG_STDOUT (" %8llu messages.\n", Modes.stat.messages_total);
+  LOG_STDOUT (" %8llu unique aircrafts.\n", Modes.stat.unique_aircrafts);
+  LOG_STDOUT (" %8llu aircrafts at peak.\n", Modes.stat.peak_aircrafts);
+  if (Modes.home_pos_ok)
+     LOG_STDOUT (" %8.1f %s max range.\n",
+                 Modes.stat.max_range / (Modes.metric ? 1000.0 : 1852.0), Modes.metric ? "km" : "Nm");
+  LOG_STDOUT (" %8llu / %llu / %llu aircrafts with ADS-B version 0 / 1 / 2.\n",
+              Modes.stat.ADSB_version[0], Modes.stat.ADSB_version[1], Modes.stat.ADSB_version[2]);
+  LOG_STDOUT (" %8llu aircrafts with NACp below 8.\n", Modes.stat.NACp_low);
+}
+
 /**
  * Our exit function. Free all resources here.
  */
@@ -5051,6 +7734,13 @@ void modeS_exit (void)
        Sleep (100);
   }
 
+  /* Let the reader thread return from `rtlsdr_read_async()` before the
+   * device is closed. Otherwise the handle could be left in a state that
+   * makes the next `rtlsdr_open()` fail.
+   */
+  if (Modes.reader_thread)
+     WaitForSingleObject ((HANDLE)Modes.reader_thread, 1000);
+
   if (Modes.rtlsdr.device)
   {
     if (Modes.bias_tee)
@@ -5072,6 +7762,35 @@ void modeS_exit (void)
   if (Modes.reader_thread)
      CloseHandle ((HANDLE)Modes.reader_thread);
 
+  if (Modes.record_error)
+     LOG_STDERR ("Recording to \"%s\" failed. It is truncated at %llu bytes.\n",
+                 Modes.record_file, Modes.record_bytes);
+  else if (Modes.record)
+     LOG_STDOUT ("Recorded %llu bytes to \"%s\".\n", Modes.record_bytes, Modes.record_file);
+
+  if (Modes.record)
+     fclose (Modes.record);
+  Modes.record = NULL;
+
+  if (Modes.pos_report)
+  {
+    LOG_STDOUT ("Wrote %llu rejected positions to \"%s\".\n", Modes.pos_report_num, Modes.pos_report_file);
+    fclose (Modes.pos_report);
+  }
+  Modes.pos_report = NULL;
+
+  if (Modes.rate_csv)
+  {
+    if (Modes.rate.messages > 0)
+       rate_next_minute();
+    show_rate_chart();
+    LOG_STDOUT ("Wrote %zu minutes to \"%s\".\n", Modes.rate_history_num, Modes.rate_csv_file);
+    fclose (Modes.rate_csv);
+  }
+  Modes.rate_csv = NULL;
+  free (Modes.rate_history);
+  Modes.rate_history = NULL;
+
   if (Modes.fd > STDIN_FILENO)
      _close (Modes.fd);
 
@@ -5193,6 +7912,7 @@ static struct option long_options[] = {
   { "bias",             no_argument,        (int*)&Modes.bias_tee,         1   },
   { "calibrate",        no_argument,        (int*)&Modes.rtlsdr.calibrate, 1   },
   { "debug",            required_argument,  NULL,                          'd' },
+  { "decode",           required_argument,  NULL,                          'X' },
   { "device",           required_argument,  NULL,                          'D' },
   { "freq",             required_argument,  NULL,                          'f' },
   { "gain",             required_argument,  NULL,                          'g' },
@@ -5206,21 +7926,31 @@ static struct option long_options[] = {
   { "loop",             optional_argument,  NULL,                          'l' },
   { "max-messages",     required_argument,  NULL,                          'm' },
   { "metric",           no_argument,        (int*)&Modes.metric,           1   },
+  { "modeac",           no_argument,        (int*)&Modes.mode_AC,          1   },
   { "net",              no_argument,        (int*)&Modes.net,              1   },
   { "net-active",       no_argument,        (int*)&Modes.net_active,       1   },
   { "net-only",         no_argument,        (int*)&Modes.net_only,         1   },
   { "net-http-port",    required_argument,  NULL,                          'x' + MODES_NET_SERVICE_HTTP },
   { "net-ri-port",      required_argument,  NULL,                          'x' + MODES_NET_SERVICE_RAW_IN },
   { "net-ro-port",      required_argument,  NULL,                          'x' + MODES_NET_SERVICE_RAW_OUT },
+  { "net-ro-mlat",      no_argument,        NULL,                          'M' },
   { "net-sbs-port",     required_argument,  NULL,                          'x' + MODES_NET_SERVICE_SBS_OUT },
   { "host-raw",         required_argument,  NULL,                          'Y' + MODES_NET_SERVICE_RAW_IN },
   { "host-sbs",         required_argument,  NULL,                          'Y' + MODES_NET_SERVICE_SBS_IN },
   { "only-addr",        no_argument,        (int*)&Modes.only_addr,        1   },
+  { "pos-report",       required_argument,  NULL,                          'P' },
   { "ppm",              required_argument,  NULL,                          'p' },
+  { "rate-csv",         required_argument,  NULL,                          'C' },
   { "raw",              no_argument,        (int*)&Modes.raw,              1   },
+  { "record",           required_argument,  NULL,                          'R' },
+  { "record-time",      required_argument,  NULL,                          'T' },
   { "samplerate",       required_argument,  NULL,                          's' },
   { "silent",           no_argument,        (int*)&Modes.silent,           1   },
   { "strip",            required_argument,  NULL,                          'S' },
+#if defined(USE_TESTS)
+  { "test",             no_argument,        NULL,                          'K' },
+#endif
+  { "web-auth",         required_argument,  NULL,                          'A' },
   { "web-page",         required_argument,  NULL,                          'w' },
   { NULL,               no_argument,        NULL,                          0   }
 };
@@ -5240,6 +7970,7 @@ static void set_long_option (int idx, const char *arg)
 void parse_cmd_line (int argc, char **argv)
 {
   char *end;
+  long  sec;
   int   c, idx = 0;
 
   while ((c = getopt_long (argc, argv, "+h?", long_options, &idx)) != EOF)
@@ -5301,6 +8032,39 @@ void parse_cmd_line (int argc, char **argv)
            Modes.logfile = optarg;
            break;
 
+      case 'R':
+           Modes.record_file = optarg;
+           break;
+
+      case 'P':
+           Modes.pos_report_file = optarg;
+           break;
+
+      case 'C':
+           Modes.rate_csv_file = optarg;
+           break;
+
+      case 'X':
+           Modes.decode_hex = optarg;
+           break;
+
+#if defined(USE_TESTS)
+      case 'K':
+           Modes.tests = true;
+           break;
+#endif
+
+      case 'M':
+           Modes.net_ro_mlat = true;
+           break;
+
+      case 'T':
+           sec = strtol (optarg, &end, 10);
+           if (end == optarg || *end != '\0' || sec < 0)
+              show_help ("Illegal '--record-time': %s.\n", optarg);
+           Modes.record_sec = (uint32_t) sec;
+           break;
+
       case 'm':
            Modes.max_messages = _atoi64 (optarg);
            break;
@@ -5314,19 +8078,19 @@ void parse_cmd_line (int argc, char **argv)
            break;
 
       case 'x' + MODES_NET_SERVICE_RAW_OUT:
-           modeS_net_services [MODES_NET_SERVICE_RAW_OUT].port = atoi (optarg);
+           handler_set_port (MODES_NET_SERVICE_RAW_OUT, optarg);
            break;
 
       case 'x' + MODES_NET_SERVICE_RAW_IN:
-           modeS_net_services [MODES_NET_SERVICE_RAW_IN].port = atoi (optarg);
+           handler_set_port (MODES_NET_SERVICE_RAW_IN, optarg);
            break;
 
       case 'x' + MODES_NET_SERVICE_HTTP:
-           modeS_net_services [MODES_NET_SERVICE_HTTP].port = atoi (optarg);
+           handler_set_port (MODES_NET_SERVICE_HTTP, optarg);
            break;
 
       case 'x' + MODES_NET_SERVICE_SBS_OUT:
-           modeS_net_services [MODES_NET_SERVICE_SBS_OUT].port = atoi (optarg);
+           handler_set_port (MODES_NET_SERVICE_SBS_OUT, optarg);
            break;
 
       case 'Y' + MODES_NET_SERVICE_RAW_OUT:
@@ -5363,6 +8127,19 @@ void parse_cmd_line (int argc, char **argv)
            Modes.interactive_ttl = 1000 * atoi (optarg);
            break;
 
+      case 'A':
+           if (!strcmp(optarg, "random"))
+           {
+             Modes.web_auth = web_auth_random();
+             if (!Modes.web_auth)
+                show_help ("Failed to generate a '--web-auth' token.\n");
+             printf ("Token for '--web-auth': %s\n", Modes.web_auth);
+           }
+           else if (!web_auth_check(optarg))
+              show_help ("Illegal '--web-auth': '%s'.\n", optarg);
+           else Modes.web_auth = optarg;
+           break;
+
       case 'w':
            strncpy (Modes.web_root, dirname(optarg), sizeof(Modes.web_root)-1);
            strncpy (Modes.web_page, basename(optarg), sizeof(Modes.web_page)-1);
@@ -5375,6 +8152,9 @@ void parse_cmd_line (int argc, char **argv)
            break;
     }
   }
+  if (Modes.record_sec && !Modes.record_file)
+     show_help ("Option '--record-time' needs '--record <file>'.\n\n");
+
   if (Modes.net_only || Modes.net_active)
      Modes.net = Modes.net_only = true;
 }
@@ -5399,6 +8179,28 @@ int main (int argc, char **argv)
   if (rc)
      goto quit;
 
+  if (Modes.decode_hex)
+  {
+    rc = decode_hex_arg (Modes.decode_hex);
+    modeS_exit();
+    return (rc);
+  }
+
+#if defined(USE_TESTS)
+  if (Modes.tests)
+  {
+    rc = modeS_tests();
+    modeS_exit();
+    return (rc);
+  }
+#endif
+
+  if (Modes.record_file && (Modes.net_only || Modes.infile))
+  {
+    LOG_STDERR ("Option `--record` needs a physical device. Ignoring it.\n");
+    Modes.record_file = NULL;
+  }
+
   if (Modes.net_only)
   {
     LOG_STDERR ("Net-only mode, no physical device or file open.\n");
@@ -5447,6 +8249,27 @@ int main (int argc, char **argv)
          goto quit;
       dev_opened = 1;
     }
+
+    if (Modes.record_file)
+    {
+      rc = modeS_init_record();
+      if (rc)
+         goto quit;
+    }
+  }
+
+  if (Modes.pos_report_file)
+  {
+    rc = modeS_init_pos_report();
+    if (rc)
+       goto quit;
+  }
+
+  if (Modes.rate_csv_file)
+  {
+    rc = modeS_init_rate_csv();
+    if (rc)
+       goto quit;
   }
 
   if (Modes.net)
@@ -5457,6 +8280,8 @@ int main (int argc, char **argv)
        goto quit;
   }
 
+  Modes.start_time = MSEC_TIME();
+
   if (Modes.infile)
   {
     rc = read_from_data_file();
@@ -5476,8 +8301,10 @@ int main (int argc, char **argv)
   }
 
 quit:
-  if (print_server_errors() == 0 && dev_opened)
+  if (print_server_errors() == 0 && (dev_opened || Modes.infile))
      show_statistics();
+  if (Modes.start_time)
+     show_session_summary();
   modeS_exit();
   return (0);
 }
@@ -56,6 +56,8 @@
 #define MODES_NET_SERVICE_HTTP      4
 #define MODES_NET_SERVICES_NUM     (MODES_NET_SERVICE_HTTP + 1)
 
+#define MODES_WEB_AUTH_MAX         64   /* Max length of the `--web-auth` argument */
+
 /**
  * \def SAFE_COND_SIGNAL(cond, mutex)
  * \def SAFE_COND_WAIT(cond, mutex)
@@ -153,6 +155,8 @@ typedef struct net_service {
         uint16_t         num_connections;  /**< Number of clients/servers connected to this service */
         bool             active_send;      /**< We are the sending side. Never duplex. */
         bool             is_ip6;           /**< The above `host` address is an IPv6 address */
+        char            *bind_addr;        /**< The address to listen on from a `--net-xx-port addr:port`. NULL for all interfaces. */
+        bool             bind_ip6;         /**< The above `bind_addr` is an IPv6 address */
         char            *last_err;         /**< Last error from a `MG_EV_ERROR` event */
         mg_timer         timer;            /**< Timer for a `mg_connect()` */
       } net_service;
@@ -188,6 +192,17 @@ typedef enum a_show_t {
         A_SHOW_NONE,
       } a_show_t;
 
+/**
+ * \enum a_suspect_t
+ * Why an aircraft is suspect. Set by `aircraft_set_suspect()`.
+ */
+typedef enum a_suspect_t {
+        A_SUSPECT_NONE = 0,
+        A_SUSPECT_JUMPS,       /**< Too many implausible position jumps in a row. */
+        A_SUSPECT_DUPLICATE,   /**< The rejected positions form a second track; a duplicate ICAO address. */
+        A_SUSPECT_TRACK,       /**< The track between the positions does not match the reported heading. */
+      } a_suspect_t;
+
 /**
  * \typedef struct pos_t
  *
@@ -223,6 +238,116 @@ typedef struct cartesian_t {
                            assert (pos.lat >= -180 && pos.lat < 180); \
                          } while (0)
 
+/**
+ * \def MODES_MAX_RANGE
+ * \def MODES_LOCAL_RANGE_AIR
+ * \def MODES_LOCAL_RANGE_SURFACE
+ *
+ * Range checks (in meters) for the CPR decoding:
+ *  \li A globally decoded position further from home than `MODES_MAX_RANGE` is taken as a bad decode.
+ *  \li A position decoded relative to home is only accepted within half a CPR zone;
+ *      `MODES_LOCAL_RANGE_AIR` for airborne and `MODES_LOCAL_RANGE_SURFACE` for surface positions.
+ */
+#define MODES_MAX_RANGE            (300 * 1852.0)
+#define MODES_LOCAL_RANGE_AIR      (180 * 1852.0)
+#define MODES_LOCAL_RANGE_SURFACE  (45 * 1852.0)
+
+/**
+ * \def MODES_MAX_SPEED
+ * \def MODES_MAX_BAD_POSITIONS
+ *
+ * Plausibility checks for a new position:
+ *  \li The speed (in knots) needed to move from the last position must be below `MODES_MAX_SPEED`.
+ *  \li After `MODES_MAX_BAD_POSITIONS` rejected positions in a row, the new position is accepted
+ *      anyway. Since the old position was probably the wrong one.
+ */
+#define MODES_MAX_SPEED            2000
+#define MODES_MAX_BAD_POSITIONS    4
+
+/**
+ * \def MODES_TRACK_MIN_DIST
+ * \def MODES_MAX_TRACK_DIFF
+ * \def MODES_MAX_BAD_TRACKS
+ *
+ * Check of the track against the reported heading:
+ *  \li The track is taken over at least `MODES_TRACK_MIN_DIST` meters.
+ *  \li A track differing more than `MODES_MAX_TRACK_DIFF` degrees from the heading is bad.
+ *  \li After `MODES_MAX_BAD_TRACKS` bad tracks in a row, the aircraft is suspect.
+ */
+#define MODES_TRACK_MIN_DIST       2000.0
+#define MODES_MAX_TRACK_DIFF       90.0
+#define MODES_MAX_BAD_TRACKS       3
+
+/**
+ * \def MODES_RANGE_BEARINGS
+ * The number of bearing sectors (2 degrees each) in the actual range outline.
+ */
+#define MODES_RANGE_BEARINGS  180
+
+/**
+ * \typedef struct range_point
+ * The position furthest away from home seen in a bearing sector.
+ */
+typedef struct range_point {
+        pos_t  pos;                 /**< Position of the aircraft */
+        double distance;            /**< Distance (in meters) to home position */
+        int    altitude;            /**< Altitude of the aircraft */
+      } range_point;
+
+/**
+ * \def MODES_AC_MIN_LEVEL
+ * \def MODES_AC_SHOW_SQUAWKS
+ *
+ * Mode A/C reply detection with option `--modeac`:
+ *  \li Both framing pulses must have a magnitude above `MODES_AC_MIN_LEVEL`.
+ *  \li The statistics show the `MODES_AC_SHOW_SQUAWKS` most seen squawks.
+ */
+#define MODES_AC_MIN_LEVEL       (10*255)
+#define MODES_AC_SHOW_SQUAWKS    10
+
+/**
+ * \def MODES_RATE_ADDRESSES
+ * The max. number of unique ICAO addresses counted in a minute with option `--rate-csv`.
+ * Must be a power of 2.
+ */
+#define MODES_RATE_ADDRESSES  1024
+
+/**
+ * \typedef struct rate_minute
+ * The messages in one minute for option `--rate-csv`.
+ */
+typedef struct rate_minute {
+        uint64_t minute;                        /**< Minute since start (of the recording with `--infile`) */
+        uint32_t messages;                      /**< Number of messages */
+        uint32_t aircrafts;                     /**< Number of unique ICAO addresses */
+        uint32_t DF [32];                       /**< Messages per Downlink Format */
+      } rate_minute;
+
+/**
+ * \def MODES_RSSI_SECTORS
+ * \def MODES_RSSI_MIN_MESSAGES
+ * \def MODES_RSSI_WEAK_DB
+ *
+ * The RSSI statistics per bearing sector (22.5 degrees each):
+ *  \li A sector needs `MODES_RSSI_MIN_MESSAGES` messages to be compared.
+ *  \li A sector is weak if its RSSI is `MODES_RSSI_WEAK_DB` below the average of all sectors.
+ */
+#define MODES_RSSI_SECTORS       16
+#define MODES_RSSI_MIN_MESSAGES  100
+#define MODES_RSSI_WEAK_DB       6.0
+
+/**
+ * \typedef struct RSSI_sector
+ * The signal-levels of messages from aircrafts in a bearing sector.
+ * Each RSSI is normalised to a distance of 1 Nm by adding the free-space loss
+ * (`20 * log10(distance)`). So near and far aircrafts can be compared.
+ */
+typedef struct RSSI_sector {
+        double   dB_sum;            /**< Sum of normalised RSSI (in dBFS) */
+        double   dist_sum;          /**< Sum of distances (in meters) */
+        uint64_t num;               /**< Number of messages */
+      } RSSI_sector;
+
 /**
  * \typedef struct aircraft
  * Structure used to describe an aircraft in interactive mode.
@@ -244,6 +369,39 @@ typedef struct aircraft {
         double   EST_distance;      /**< Estimated `distance` based on last `speed` and `heading` */
         double   sig_levels [4];    /**< RSSI signal-levels from the last 4 messages */
         int      sig_idx;
+        double   sig_min;           /**< Lowest signal-level seen */
+        double   sig_max;           /**< Highest signal-level seen */
+        double   sig_sum;           /**< Sum of signal-levels for an average */
+        uint32_t sig_num;           /**< Number of messages in `sig_sum` */
+        double   sig_t_sum;         /**< For a least-squares RSSI trend: sum of times (sec since `seen_first`), */
+        double   sig_tt_sum;        /**< sum of squared times, */
+        double   sig_dB_sum;        /**< sum of RSSI (dBFS) */
+        double   sig_t_dB_sum;      /**< and sum of time * RSSI. */
+
+        /* Data quality from the Operational Status (ME type 31) and position messages.
+         */
+        bool     op_status_ok;      /**< Have received an Operational Status message. */
+        int      ADSB_version;      /**< ADS-B version number (0 - 2). */
+        int      NACp;              /**< Navigation Accuracy Category for position. */
+        int      SIL;               /**< Source Integrity Level. */
+        bool     NIC_supp_A;        /**< NIC supplement-A. */
+        int      NIC;               /**< Navigation Integrity Category from the last position. -1 if unknown. */
+
+        /* Data from the Comm-B registers in DF20 and DF21 replies.
+         */
+        bool     BDS_40_ok;         /**< Have received a BDS 4,0 register. */
+        int      sel_altitude;      /**< BDS 4,0: MCP/FCU selected altitude (feet). */
+        double   baro_setting;      /**< BDS 4,0: barometric pressure setting (mb). 0 if not available. */
+        bool     BDS_50_ok;         /**< Have received a BDS 5,0 register. */
+        double   roll;              /**< BDS 5,0: roll angle (degrees). */
+        double   true_track;        /**< BDS 5,0: true track angle (degrees). */
+        int      ground_speed;      /**< BDS 5,0: ground speed (knots). */
+        int      true_airspeed;     /**< BDS 5,0: true airspeed (knots). 0 if not available. */
+        bool     BDS_60_ok;         /**< Have received a BDS 6,0 register. */
+        double   mag_heading;       /**< BDS 6,0: magnetic heading (degrees). */
+        int      IAS;               /**< BDS 6,0: indicated airspeed (knots). */
+        double   mach;              /**< BDS 6,0: Mach number. */
+        int      baro_vert_rate;    /**< BDS 6,0: barometric altitude rate (feet/min). */
 
         /* Encoded latitude and longitude as extracted by odd and even
          * CPR encoded messages.
@@ -256,6 +414,14 @@ typedef struct aircraft {
         uint64_t even_CPR_time;     /**< Tick-time for reception of an even CPR message */
         pos_t    position;          /**< Coordinates obtained from decoded CPR data. */
         pos_t    EST_position;      /**< Estimated position based on last `speed` and `heading`. */
+        uint64_t position_time;     /**< Tick-time for the last accepted position. */
+        uint64_t msg_time;          /**< Time (in milli-sec) of the last message for the position checks. */
+        uint32_t bad_positions;     /**< Number of positions in a row rejected by `position_plausible()`. */
+        pos_t    rejected_position; /**< The last position rejected by `position_plausible()`. */
+        uint64_t rejected_time;     /**< Tick-time of `rejected_position`. */
+        pos_t    track_position;    /**< Start of the track to compare with `heading`. */
+        uint32_t bad_tracks;        /**< Number of tracks in a row not matching `heading`. */
+        a_suspect_t suspect;        /**< Implausible positions or track seen; a ghost or duplicate ICAO address? */
 
         const aircraft_CSV *CSV;  /**< A pointer to a CSV record (or NULL). */
         struct aircraft    *next; /**< Next aircraft in our linked list. */
@@ -278,6 +444,22 @@ typedef struct statistics {
         uint64_t  unique_aircrafts_CSV;
         uint64_t  unrecognized_ME;
         uint64_t  messages_total;
+        uint64_t  peak_aircrafts;      /* Max number of aircrafts at the same time */
+        uint64_t  CPR_global_ok;       /* Good global CPR decodes */
+        uint64_t  CPR_local_ok;        /* Good CPR decodes relative to home */
+        uint64_t  CPR_range_errors;    /* CPR decodes rejected by a range check */
+        uint64_t  CPR_speed_errors;    /* Positions rejected by a speed check */
+        uint64_t  suspect_aircrafts;   /* Aircrafts with implausible positions */
+        double    max_range;           /* Max distance (in meters) to any aircraft */
+        uint64_t  DF_total  [32];      /* Messages per Downlink Format */
+        uint64_t  DF_CRC_ok [32];      /* Messages per DF with CRC okay */
+        uint64_t  DF_fixed  [32];      /* Messages per DF with errors corrected */
+        uint64_t  ADSB_version [3];    /* Aircrafts per ADS-B version */
+        uint64_t  NACp_low;            /* Aircrafts with a NACp below 8 (EPU >= 0.05 Nm) */
+        RSSI_sector RSSI_sectors [MODES_RSSI_SECTORS];  /* RSSI per bearing sector from home */
+        uint64_t  AC_replies;          /* Mode A/C replies detected */
+        uint64_t  AC_matched;          /* Mode A/C replies with the squawk of a Mode S aircraft */
+        uint64_t  AC_squawks [4096];   /* Mode A/C replies per squawk (octal ABCD as 12 bits) */
 
         /* Network statistics:
          */
@@ -290,10 +472,13 @@ typedef struct statistics {
         uint64_t  bytes_sent [MODES_NET_SERVICES_NUM];
         uint64_t  bytes_recv [MODES_NET_SERVICES_NUM];
         uint64_t  HTTP_get_requests;
+        uint64_t  HTTP_JSON_requests;
+        uint64_t  HTTP_JSON_bytes;
         uint64_t  HTTP_keep_alive_recv;
         uint64_t  HTTP_keep_alive_sent;
         uint64_t  HTTP_websockets;
         uint64_t  HTTP_404_responses;
+        uint64_t  HTTP_401_responses;
 
         /* Network statistics for receiving raw and SBS messages:
          */
@@ -399,6 +584,12 @@ typedef struct global_data {
         mg_connection *raw_in;                 /**< Raw input listening connection. */
         mg_connection *http_out;               /**< HTTP listening connection. */
         mg_mgr         mgr;                    /**< Only one connection manager */
+        uint64_t       net_start;              /**< The `MSEC_TIME()` when the network was started. */
+        uint64_t       net_rate_time;          /**< The `MSEC_TIME()` of the last `net_rates_update()`. */
+        uint64_t       net_rate_bytes [MODES_NET_SERVICES_NUM];  /**< `stat.bytes_sent[]` at `net_rate_time`. */
+        double         net_rate [MODES_NET_SERVICES_NUM];        /**< Bytes/sec sent in the last `MODES_NET_RATE_INTERVAL`. */
+        uint64_t       net_rate_JSON;          /**< `stat.HTTP_JSON_requests` at `net_rate_time`. */
+        double         JSON_poll_rate;         /**< JSON requests/sec in the last `MODES_NET_RATE_INTERVAL`. */
 
         /** Aircraft history
          */
@@ -411,9 +602,28 @@ typedef struct global_data {
         const char *infile;                    /**< Input IQ samples from file with option `--infile file`. */
         const char *logfile;                   /**< Write debug/info to file with option `--logfile file`. */
         FILE       *log;
+        const char *record_file;               /**< Write IQ samples to file with option `--record file`. */
+        FILE       *record;
+        uint32_t    record_sec;                /**< Stop after recording this many seconds. 0 is no limit. */
+        uint64_t    record_bytes;              /**< Number of IQ bytes written so far. */
+        bool        record_error;              /**< A write to the `--record` file failed. */
+        const char *pos_report_file;           /**< Write the rejected positions to file with option `--pos-report file`. */
+        FILE       *pos_report;
+        uint64_t    pos_report_num;            /**< Number of rejected positions written so far. */
+        const char *rate_csv_file;             /**< Write the messages per minute to file with option `--rate-csv file`. */
+        FILE       *rate_csv;
+        rate_minute rate;                      /**< The current minute for `--rate-csv`. */
+        uint32_t    rate_addr [MODES_RATE_ADDRESSES];  /**< A hash-set of the ICAO addresses in this minute. */
+        rate_minute *rate_history;             /**< All the minutes written; for the chart on exit. */
+        size_t      rate_history_num;
+        const char *decode_hex;                /**< Decode and show a single message with option `--decode hex`. */
+        bool        tests;                     /**< Run the CPR decoder self-tests with option `--test` (needs `USE_TESTS`). */
+        uint64_t    start_time;                /**< The `MSEC_TIME()` when processing started. */
         uint64_t    loops;                     /**< Read input file in a loop. */
         uint32_t    debug;                     /**< Debugging mode bits. */
         bool        raw;                       /**< Raw output format. */
+        bool        net_ro_mlat;               /**< Write `@` or `<` timestamped raw output with option `--net-ro-mlat`. */
+        uint64_t    sample_counter;            /**< Number of samples processed; for the MLAT timestamp. */
         bool        net;                       /**< Enable networking. */
         bool        net_only;                  /**< Enable just networking. */
         bool        net_active;                /**< With `Modes.net`, call `connect()` (not `listen()`). */
@@ -424,13 +634,16 @@ typedef struct global_data {
         bool        only_addr;                 /**< Print only ICAO addresses. */
         bool        metric;                    /**< Use metric units. */
         bool        aggressive;                /**< Aggressive detection algorithm. */
+        bool        mode_AC;                   /**< Detect Mode A/C replies too with option `--modeac`. */
         char        web_page [MG_PATH_MAX];    /**< The base-name of the web-page to server for HTTP clients */
         char        web_root [MG_PATH_MAX];    /**< And it's directory */
+        const char *web_auth;                  /**< The `user:password` or token required for HTTP clients. */
         char        aircraft_db [MG_PATH_MAX]; /**< The `aircraftDatabase.csv` file */
         int         strip_level;               /**< For '--strip X' mode */
         pos_t       home_pos;                  /**< Coordinates of home position */
         cartesian_t home_pos_cart;             /**< Coordinates of home position (cartesian) */
         bool        home_pos_ok;               /**< We have a good home position */
+        range_point range_outline [MODES_RANGE_BEARINGS]; /**< The actual range outline around home position */
 
         /** For parsing a `Modes.aircraft_db` file:
          */
@@ -2,20 +2,61 @@
 
 """
 Simple Python script for Dump1090 testing:
-Supported modes: RAW-OUT, RAW-IN and SBS.
+Supported modes: RAW-OUT, RAW-IN, SBS and BEAST.
 
 RAW-OUT server: Connect to host at port 30001 and send '*...;' messages and print it to console.
-RAW-IN client:  Connect to host at port 30002, receive '*...;' messages and print it to console.
+                The DF17 messages are generated for some random airplanes near the home-position.
+                With '--fuzz', a percentage of the messages are mutated (bit-errors, bad length etc.).
+RAW-IN client:  Connect to host at port 30002, receive '*...;' (or '@...;' and '<...;') messages and print it to console.
+                The framing and CRC of each message is checked and statistics are printed.
 SBS client:     Connect to host at port 30003, listen for 'MSG,' text and print it to console.
+                Malformed records are flagged and a summary of the aircraft seen is printed.
+BEAST client:   Connect to host at '--port', check the binary Beast framing, decode some frames
+                and print statistics on message types and rates.
+                This Dump1090 has no Beast output. This mode is only for other decoders
+                (e.g. 'dump1090-fa' or 'readsb' at port 30005). Hence '--port' is required.
 """
 
-import sys, os, time, argparse, socket
+import sys, os, time, argparse, socket, random, math
 
 REMOTE_HOST  = "localhost"
 RAW_OUT_PORT = 30001
 RAW_IN_PORT  = 30002
 SBS_PORT     = 30003
 
+#
+# The home-position for generated RAW-OUT positions, if
+# the env-var 'DUMP1090_HOMEPOS' is not set.
+#
+HOME_POS     = (51.5, 0.0)
+
+#
+# The Mode S character set for the aircraft identification.
+#
+AIS_CHARSET = "#ABCDEFGHIJKLMNOPQRSTUVWXYZ##### ###############0123456789######"
+
+#
+# Altitudes to test the edge-cases of the 12-bit altitude field.
+#
+EDGE_ALTITUDES = [ -1000, 0, 25, 50175 ]
+
+#
+# Beast frame-types and the length of each message (excluding the
+# 6 byte timestamp and 1 byte signal-level).
+#
+BEAST_ESC   = 0x1A
+BEAST_TYPES = { ord("1"): 2,    # Mode A/C
+                ord("2"): 7,    # Mode S short
+                ord("3"): 14 }  # Mode S long
+BEAST_SHOW  = 20                # Decode and print this many frames
+
+#
+# The names of the SBS / BaseStation 'MSG' fields 10 - 21.
+# A 'MSG' record has 22 fields in total.
+#
+SBS_FIELDS = [ "callsign", "altitude", "speed", "heading", "lat", "lon",
+               "vert-rate", "squawk", "alert", "emergency", "SPI", "on-ground" ]
+
 class cfg():
   quit = False
   logf = None
@@ -24,6 +65,24 @@ class cfg():
   format   = "?? %d bytes\n"
   data_len = 0
   sleep = 1
+  start = 0
+  beast_buf    = b""
+  beast_frames = {}
+  beast_DF     = {}
+  beast_sync_err = 0
+  beast_esc_err  = 0
+  sbs_aircraft   = {}
+  sbs_records    = 0
+  sbs_bad        = 0
+  raw_frames     = 0
+  raw_bad_frame  = 0
+  raw_crc_ok     = 0
+  raw_crc_bad    = 0
+  raw_DF         = {}
+  raw_planes     = []
+  raw_sent       = 0
+  raw_mutated    = {}
+  fuzz           = 0
 
 #
 # Print to both stdout and log-file
@@ -42,11 +101,13 @@ def show_help (error=None):
     sys.exit (1)
 
   print (__doc__[1:])
-  print ("""Usage: %s [options] [RAW-OUT | RAW-IN | SBS]
+  print ("""Usage: %s [options] [RAW-OUT | RAW-IN | SBS | BEAST]
   -h, --help: Show this help.
   --host      Host to connect to.
-  --port      TCP port to connect to.
-  --wait      Seconds to wait before connecting (default=0).""" % __file__)
+  --port      TCP port to connect to (required in BEAST mode).
+  --wait      Seconds to wait before connecting (default=0).
+  --planes    Number of airplanes to generate in RAW-OUT mode (default=5).
+  --fuzz      Percentage of mutated RAW-OUT messages (default=0).""" % __file__)
 
 def parse_cmdline():
   parser = argparse.ArgumentParser (add_help=False)
@@ -54,6 +115,8 @@ def parse_cmdline():
   parser.add_argument ("--host",       dest = "host", type = str, default = REMOTE_HOST)
   parser.add_argument ("--port",       dest = "port", type = int, default = 0)
   parser.add_argument ("--wait",       dest = "wait", type = int, default = 0)
+  parser.add_argument ("--planes",     dest = "planes", type = int, default = 5)
+  parser.add_argument ("--fuzz",       dest = "fuzz", type = int, default = 0)
   parser.add_argument ("mode", nargs = argparse.REMAINDER)
   return parser.parse_args()
 
@@ -69,6 +132,71 @@ def connect_to_host (opt):
     cfg.logf.close()
     sys.exit (1)
 
+#
+# Compute the Mode S CRC over the whole message (including the parity field).
+# The result is the syndrome; 0 for a good DF11/17/18 message. For the other
+# DFs, the parity is overlaid with the ICAO address.
+#
+def modes_crc (msg):
+  crc = 0
+  for byte in msg:
+    crc ^= byte << 16
+    for i in range(8):
+      crc <<= 1
+      if crc & 0x1000000:
+        crc ^= 0x1FFF409
+  return crc
+
+#
+# Check the framing and CRC of one '*...;' line.
+# With '--net-ro-mlat', dump1090 also sends '@<timestamp>...;' and
+# '<<timestamp><signal>...;' lines. The timestamp is 12 hex-digits
+# and the signal-level is 2 hex-digits; these are stripped.
+# Returns an error-string if something is wrong.
+#
+def raw_check (line):
+  line = line.rstrip ("\r\n")
+  if not line.endswith(";"):
+    return "bad framing"
+  if line.startswith("*"):
+    hex_str = line[1:-1]
+  elif line.startswith("@"):
+    hex_str = line[1+12:-1]
+  elif line.startswith("<"):
+    hex_str = line[1+12+2:-1]
+  else:
+    return "bad framing"
+  try:
+    msg = bytes.fromhex (hex_str)
+  except ValueError:
+    return "not hex"
+  if len(msg) not in (2, 7, 14):     # Mode A/C, Mode S short or long
+    return "bad length %d" % len(msg)
+
+  cfg.raw_frames += 1
+  if len(msg) == 2:
+    DF = "A/C"
+  else:
+    DF = msg[0] >> 3
+  cfg.raw_DF [DF] = cfg.raw_DF.get(DF, 0) + 1
+  if DF not in (11, 17, 18):
+    return None
+
+  crc = modes_crc (msg)
+  if DF == 11:
+    crc &= ~0x7F                         # The interrogator identifier
+  if crc:
+    cfg.raw_crc_bad += 1
+    return "bad CRC, DF%d, syndrome %06X" % (DF, crc)
+  cfg.raw_crc_ok += 1
+  return None
+
+def raw_statistics():
+  modes_log ("Got %d well-formed frames, %d malformed.\n" % (cfg.raw_frames, cfg.raw_bad_frame))
+  modes_log ("  CRC OK: %d, CRC bad: %d (only DF11/17/18 checked)\n" % (cfg.raw_crc_ok, cfg.raw_crc_bad))
+  for DF in sorted(cfg.raw_DF, key = str):
+    modes_log ("  DF%-3s %6d\n" % (DF, cfg.raw_DF[DF]))
+
 #
 # For receiving RAW-IN messages.
 #
@@ -76,10 +204,12 @@ def raw_in_loop (sock):
   do_sleep = True
   try:
     if 1:
-      data = sock.readline (10)
+      data = sock.readline()
     else:
       data = sock.recv (100, socket.MSG_WAITALL)
     # print (data)
+    if not data:     # EOF
+      do_sleep = False
   except:
     do_sleep = False
     print ("do_sleep = False")
@@ -88,6 +218,11 @@ def raw_in_loop (sock):
   if data:
     modes_log (data)
     cfg.data_len += len(data)
+    error = raw_check (data)
+    if error:
+      if not error.startswith("bad CRC"):
+        cfg.raw_bad_frame += 1
+      modes_log ("Bad message: %s.\n" % error)
     time.sleep (cfg.sleep)
   elif do_sleep:
     time.sleep (cfg.sleep)
@@ -95,6 +230,48 @@ def raw_in_loop (sock):
     modes_log ("Connection gone.\n")
     cfg.quit = True
 
+#
+# Check one SBS record and add the populated fields to the aircraft seen.
+# Returns an error-string if the record is malformed.
+#
+def sbs_check (line):
+  fields = line.rstrip("\r\n").split (",")
+  if fields[0] != "MSG":
+    return "not a 'MSG' record"
+  if len(fields) != 22:
+    return "%d fields; expected 22" % len(fields)
+  if fields[1] not in "12345678" or len(fields[1]) != 1:
+    return "bad transmission type '%s'" % fields[1]
+  try:
+    ICAO = int (fields[4], 16)
+  except ValueError:
+    return "bad hex-ident '%s'" % fields[4]
+  if len(fields[4]) != 6:
+    return "bad hex-ident '%s'" % fields[4]
+
+  for i, value in enumerate(fields[11:], 11):
+    if value == "":
+      continue
+    try:
+      float (value)
+    except ValueError:
+      return "field '%s' is not numeric: '%s'" % (SBS_FIELDS[i-10], value)
+
+  a = cfg.sbs_aircraft.setdefault (ICAO, { "msgs": 0, "fields": set() })
+  a ["msgs"] += 1
+  for i, value in enumerate(fields[10:], 10):
+    if value != "":
+      a ["fields"].add (SBS_FIELDS[i-10])
+  return None
+
+def sbs_statistics():
+  modes_log ("Got %d SBS records (%d malformed) from %d aircraft.\n" %
+             (cfg.sbs_records, cfg.sbs_bad, len(cfg.sbs_aircraft)))
+  for ICAO in sorted(cfg.sbs_aircraft):
+    a = cfg.sbs_aircraft [ICAO]
+    fields = [ f for f in SBS_FIELDS if f in a["fields"] ]
+    modes_log ("  %06X: %5d msgs, %s\n" % (ICAO, a["msgs"], ", ".join(fields) or "no fields"))
+
 #
 # For receiving SBS messages.
 #
@@ -107,29 +284,247 @@ def sbs_in_loop (sock):
   else:
     modes_log (data)
     cfg.data_len += len(data)
+    if data.strip():
+      cfg.sbs_records += 1
+      error = sbs_check (data)
+      if error:
+        cfg.sbs_bad += 1
+        modes_log ("Malformed record: %s.\n" % error)
     time.sleep (cfg.sleep)
 
+#
+# Decode and (for the first 'BEAST_SHOW' frames) print one unescaped Beast frame.
+#
+def beast_frame (ftype, frame):
+  cfg.beast_frames [ftype] = cfg.beast_frames.get(ftype, 0) + 1
+  msg = frame[7:]
+  if ftype == ord("1"):
+    descr = "Mode A/C"
+  else:
+    DF = msg[0] >> 3
+    cfg.beast_DF [DF] = cfg.beast_DF.get(DF, 0) + 1
+    descr = "DF%-2d" % DF
+    if DF in (11, 17, 18):
+      descr += ", ICAO %06X" % int.from_bytes(msg[1:4], "big")
+
+  if sum(cfg.beast_frames.values()) <= BEAST_SHOW:
+    modes_log ("Beast '%c': ts %012X, sig %3d, %-18s %s\n" %
+               (ftype, int.from_bytes(frame[0:6], "big"), frame[6], descr + ",", msg.hex().upper()))
+
+#
+# Parse the Beast frames in 'buf'.
+# Returns the remaining incomplete data.
+#
+def beast_parse (buf):
+  i = 0
+  while True:
+    skip = i
+    while i < len(buf) and buf[i] != BEAST_ESC:
+      i += 1
+    if i > skip:
+      modes_log ("Sync error: skipped %d bytes.\n" % (i - skip))
+      cfg.beast_sync_err += 1
+
+    if i + 2 > len(buf):
+      return buf[i:]
+
+    ftype = buf[i+1]
+    if ftype not in BEAST_TYPES:
+      modes_log ("Sync error: unknown frame-type 0x%02X.\n" % ftype)
+      cfg.beast_sync_err += 1
+      i += 1
+      continue
+
+    need  = 6 + 1 + BEAST_TYPES[ftype]
+    frame = bytearray()
+    bad   = False
+    j = i + 2
+    while len(frame) < need and j < len(buf):
+      if buf[j] == BEAST_ESC:
+        if j + 1 >= len(buf):
+          break
+        if buf[j+1] != BEAST_ESC:  # A lone 0x1A inside a frame
+          bad = True
+          break
+        j += 1
+      frame.append (buf[j])
+      j += 1
+
+    if bad:
+      modes_log ("Escape error: frame-type '%c' truncated after %d bytes.\n" % (ftype, len(frame)))
+      cfg.beast_esc_err += 1
+      i = j
+      continue
+
+    if len(frame) < need:
+      return buf[i:]
+
+    beast_frame (ftype, frame)
+    i = j
+
+#
+# For receiving BEAST messages.
+#
+def beast_in_loop (sock):
+  try:
+    data = sock.recv (1024)
+  except socket.timeout:
+    return
+
+  if not data:
+    modes_log ("Connection gone.\n")
+    cfg.quit = True
+  else:
+    cfg.data_len += len(data)
+    cfg.beast_buf = beast_parse (cfg.beast_buf + data)
+
+def beast_statistics():
+  elapsed = max (time.time() - cfg.start, 0.001)
+  total   = sum (cfg.beast_frames.values())
+  modes_log ("Got %d Beast frames in %.1f sec (%.1f frames/sec).\n" % (total, elapsed, total / elapsed))
+  for ftype in sorted(cfg.beast_frames):
+    modes_log ("  type '%c': %6d  (%.1f/sec)\n" % (ftype, cfg.beast_frames[ftype], cfg.beast_frames[ftype] / elapsed))
+  for DF in sorted(cfg.beast_DF):
+    modes_log ("  DF%-2d:     %6d\n" % (DF, cfg.beast_DF[DF]))
+  modes_log ("  sync errors: %d, escape errors: %d\n" % (cfg.beast_sync_err, cfg.beast_esc_err))
+
 #
 # For sending RAW-OUT messages.
 #
 # Simulate a Dump1090 RAW-IN client for testing
 #   read_from_client()
 #
-# Todo:
-#  Send random messages from several airplanes by constructing
-#  realistics messages on the fly. Messages with positions close
-#  to home. Show the distance in `--interactive` mode.
+# Random messages from several airplanes are constructed on the fly.
+# With positions close to home. Dump1090 shows the distance in `--interactive` mode.
+#
+
+#
+# The number of CPR longitude zones for a latitude.
+#
+def cpr_NL (lat):
+  if abs(lat) >= 87:
+    return 1
+  if lat == 0:
+    return 59
+  a = 1 - math.cos (math.pi / 30)
+  b = math.cos (math.pi * abs(lat) / 180) ** 2
+  return int (2 * math.pi / math.acos(1 - a / b))
+
+#
+# CPR encode an airborne position. Returns the 17-bit lat and lon.
+#
+def cpr_encode (lat, lon, odd):
+  dlat = 360.0 / (60 - odd)
+  yz   = int (math.floor(131072 * (lat % dlat) / dlat + 0.5))
+  rlat = dlat * (yz / 131072 + math.floor(lat / dlat))
+  dlon = 360.0 / max (cpr_NL(rlat) - odd, 1)
+  xz   = int (math.floor(131072 * (lon % dlon) / dlon + 0.5))
+  return (yz & 0x1FFFF, xz & 0x1FFFF)
+
+#
+# Encode an altitude (in ft) into the 12-bit field with the Q-bit set.
+#
+def alt_encode (alt):
+  n = (alt + 1000) // 25
+  n = min (max(n, 0), 0x7FF)
+  return ((n & 0x7F0) << 1) | 0x10 | (n & 0x0F)
+
+#
+# Build a DF17 message from the ICAO address and 56-bit ME field.
+#
+def df17_message (addr, me):
+  msg = bytes([ (17 << 3) | 5 ]) + addr.to_bytes(3, "big") + me.to_bytes(7, "big")
+  return msg + modes_crc(msg).to_bytes(3, "big")
+
+def ident_message (plane):
+  me = (4 << 3)      # TC=4, CA=0
+  for ch in plane["callsign"].ljust(8)[:8]:
+    me = (me << 6) | AIS_CHARSET.index(ch)
+  return df17_message (plane["addr"], me)
+
+def position_message (plane):
+  plane["odd"] ^= 1
+  lat, lon = cpr_encode (plane["lat"], plane["lon"], plane["odd"])
+  me = (11 << 51) | (alt_encode(plane["alt"]) << 36) | (plane["odd"] << 34) | (lat << 17) | lon
+  return df17_message (plane["addr"], me)
+
+def raw_out_planes (num):
+  home = HOME_POS
+  env  = os.getenv ("DUMP1090_HOMEPOS")
+  if env:
+    home = tuple ([float(x) for x in env.split(",")])
+
+  for i in range(num):
+    plane = { "addr":     random.randint (0x100000, 0xFFFFFF),
+              "callsign": "TST%04d" % random.randint (0, 9999),
+              "lat":      home[0] + random.uniform (-1, 1),
+              "lon":      home[1] + random.uniform (-1, 1),
+              "alt":      random.choice (EDGE_ALTITUDES + [ random.randint(1000, 40000) ]),
+              "odd":      0 }
+    cfg.raw_planes.append (plane)
+    modes_log ("Plane %06X: %s at %.3f,%.3f, %d ft\n" %
+               (plane["addr"], plane["callsign"], plane["lat"], plane["lon"], plane["alt"]))
+
+#
+# Mutate a message to test the Dump1090 RAW-IN parser and CRC-fixing.
+# Returns the kind of the mutation and a '*...;' string.
 #
+def raw_mutate (msg):
+  kind = random.choice (["1-bit", "2-bit", "truncated", "too-long", "odd-length", "bad-hex", "bad-framing"])
+  data = bytearray (msg)
+  hex  = None
+
+  if kind == "1-bit" or kind == "2-bit":
+    for bit in random.sample (range(8 * len(data)), 1 if kind == "1-bit" else 2):
+      data [bit // 8] ^= 1 << (7 - bit % 8)
+  elif kind == "truncated":
+    data = data [:random.randint (0, len(data) - 1)]
+  elif kind == "too-long":
+    data += bytes ([ random.randint(0, 255) for i in range(random.randint(1, 10)) ])
+  elif kind == "odd-length":
+    hex = "*%s;" % data.hex()[:-1]
+  elif kind == "bad-hex":
+    hex = list (data.hex())
+    hex [random.randint(0, len(hex)-1)] = random.choice ("gxz-. ")
+    hex = "*%s;" % "".join(hex)
+  else:
+    hex = random.choice (["%s;", "*%s", "%s"]) % data.hex()
+
+  if not hex:
+    hex = "*%s;" % data.hex()
+  return (kind, hex)
+
 def raw_out_loop (sock):
-  data = b"*8d4b969699155600e87406f5b69f;\n"
-  modes_log ("Sending RAW message: %s.\n" % str(data))
-  rc = sock.send (data)
+  if not cfg.raw_planes:
+    raw_out_planes (opt.planes)
+
+  plane = random.choice (cfg.raw_planes)
+  if random.randint(0, 9) == 0:
+       msg = ident_message (plane)
+  else:
+       msg = position_message (plane)
+
+  if random.randint(1, 100) <= cfg.fuzz:
+    kind, line = raw_mutate (msg)
+    cfg.raw_mutated[kind] = cfg.raw_mutated.get (kind, 0) + 1
+    modes_log ("Sending RAW message: %s (%s).\n" % (line, kind))
+  else:
+    line = "*%s;" % msg.hex()
+    modes_log ("Sending RAW message: %s.\n" % line)
+
+  rc = sock.send (bytes(line + "\n", encoding="ascii"))
   if rc > 0:
     cfg.data_len += rc
+    cfg.raw_sent += 1
   else:
     raise (ConnectionResetError)
-  for i in range(cfg.sleep):
-    time.sleep (1)
+  time.sleep (cfg.sleep)
+
+def raw_out_statistics():
+  modes_log ("RAW-OUT statistics:\n")
+  modes_log ("  messages sent: %d\n" % cfg.raw_sent)
+  for kind in sorted(cfg.raw_mutated):
+    modes_log ("  %-12s %6d\n" % (kind + ":", cfg.raw_mutated[kind]))
 
 ### main() ####################################
 
@@ -141,7 +536,7 @@ if len(opt.mode) == 0:
   show_help ("Missing 'mode'. Use '%s -h' for usage" % __file__)
 
 mode = opt.mode[0].upper()
-if mode != "SBS" and mode != "RAW-IN" and mode != "RAW-OUT":
+if mode != "SBS" and mode != "RAW-IN" and mode != "RAW-OUT" and mode != "BEAST":
   show_help ("Unknown 'mode = %s'. Use '%s -h' for usage" % (opt.mode[0], __file__))
 
 if opt.port == 0:
@@ -151,6 +546,8 @@ if opt.port == 0:
     opt.port = RAW_IN_PORT
   elif mode == "RAW-OUT":
     opt.port = RAW_OUT_PORT
+  elif mode == "BEAST":
+    show_help ("BEAST mode needs a '--port'. Dump1090 has no Beast output; use the port of another decoder")
 
 modes_log ("Connecting to %s:%d\n" % (opt.host, opt.port))
 
@@ -164,7 +561,8 @@ if opt.wait:
 cfg.sock = connect_to_host (opt)
 
 if mode == "RAW-OUT":
-  cfg.sleep  = 1
+  cfg.sleep  = 0.2
+  cfg.fuzz   = opt.fuzz
   cfg.loop   = raw_out_loop
   cfg.format = "Sent %d bytes\n"
 elif mode == "RAW-IN":
@@ -173,12 +571,18 @@ elif mode == "RAW-IN":
   cfg.format = "Received %d bytes\n"
   #cfg.sock.setblocking (False)
   cfg.sock   = cfg.sock.makefile (mode="r")
+elif mode == "BEAST":
+  cfg.loop   = beast_in_loop
+  cfg.format = "Received %d bytes\n"
+  cfg.sock.settimeout (1)
 else:
   cfg.sleep  = 0.01
   cfg.loop   = sbs_in_loop
   cfg.format = "Received %d bytes\n"
   cfg.sock   = cfg.sock.makefile (mode="r")
 
+cfg.start = time.time()
+
 try:
   while not cfg.quit:
     cfg.loop (cfg.sock)
@@ -192,5 +596,13 @@ except KeyboardInterrupt:
   cfg.quit = True
 
 modes_log (cfg.format % cfg.data_len)
+if mode == "BEAST":
+  beast_statistics()
+elif mode == "SBS":
+  sbs_statistics()
+elif mode == "RAW-IN":
+  raw_statistics()
+elif mode == "RAW-OUT":
+  raw_out_statistics()
 cfg.sock.close()
 cfg.logf.close()